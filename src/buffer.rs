@@ -2,23 +2,22 @@
 use super::context::{Context};
 use super::dispatch::{StagingBuffer};
 
-use super::error::Result;
 use std::sync::Arc;
 
 pub struct Buffer {
     context: Arc<Context>,
     staging_buffer: Arc<StagingBuffer>,
-    region_count: usize,
+    region_sizes: Vec<usize>,
 }
 
 impl Buffer {
     pub fn new(context: &Arc<Context>, region_sizes: Vec<usize>) -> Arc<Self> {
         let command_pool = context.command_pool();
-        let staging_buffer = StagingBuffer::new(&command_pool, region_sizes.as_slice());
+        let staging_buffer = StagingBuffer::new(command_pool, region_sizes.as_slice(), context.profiler());
         let buffer = Buffer {
             context: Arc::clone(context),
             staging_buffer: staging_buffer,
-            region_count: region_sizes.len(),
+            region_sizes,
         };
         Arc::new(buffer)
     }
@@ -35,6 +34,12 @@ impl Buffer {
     
     #[inline]
     pub(crate) fn region_count(&self) -> usize {
-        self.region_count
+        self.region_sizes.len()
+    }
+
+    // the sizes the buffer is created with, e.g. to allocate another one laid out the same
+    #[inline]
+    pub fn region_sizes(&self) -> &[usize] {
+        &self.region_sizes
     }
 }
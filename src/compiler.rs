@@ -0,0 +1,195 @@
+
+use super::context::{Context};
+use super::device::{ShaderModule, ShaderModuleSource};
+use super::pipeline::{ShaderSource, ShaderWatch, IncludedFiles};
+
+use super::error::Result;
+use super::error::{ErrorCode, ShaderDiagnostic};
+use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// the Vulkan version the GLSL is compiled for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TargetEnv {
+    Vulkan1_0,
+    Vulkan1_1,
+    Vulkan1_2,
+}
+
+impl TargetEnv {
+    fn env_version(&self) -> shaderc::EnvVersion {
+        match self {
+            TargetEnv::Vulkan1_0 => shaderc::EnvVersion::Vulkan1_0,
+            TargetEnv::Vulkan1_1 => shaderc::EnvVersion::Vulkan1_1,
+            TargetEnv::Vulkan1_2 => shaderc::EnvVersion::Vulkan1_2,
+        }
+    }
+}
+
+// GLSL compute shader compiled into SPIR-V on build
+#[derive(Clone)]
+pub struct GlslSource {
+    source: GlslText,
+    defines: Vec<(String, Option<String>)>,
+    include_dirs: Vec<PathBuf>,
+    target_env: TargetEnv,
+}
+
+#[derive(Clone)]
+enum GlslText {
+    FilePath(String),
+    Text(String),
+}
+
+// the name diagnostics refer to the source given as text
+const TEXT_SOURCE_NAME: &str = "<source>";
+
+impl GlslSource {
+    pub fn from_file(filename: impl Into<String>) -> Self {
+        Self::new(GlslText::FilePath(filename.into()))
+    }
+
+    pub fn from_text(source: impl Into<String>) -> Self {
+        Self::new(GlslText::Text(source.into()))
+    }
+
+    fn new(source: GlslText) -> Self {
+        GlslSource {
+            source,
+            defines: vec![],
+            include_dirs: vec![],
+            // matches the Makefile compiling ./data
+            target_env: TargetEnv::Vulkan1_1,
+        }
+    }
+
+    // same as `#define name value` at the beginning of the source
+    pub fn define(mut self, name: impl Into<String>, value: Option<String>) -> Self {
+        self.defines.push((name.into(), value));
+        self
+    }
+
+    // searched for `#include <...>` and for `#include "..."` not found next to the includer
+    pub fn include_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(path.into());
+        self
+    }
+
+    pub fn target_env(mut self, target_env: TargetEnv) -> Self {
+        self.target_env = target_env;
+        self
+    }
+
+    pub fn compile(self) -> Result<Vec<u8>> {
+        self.compile_recording(&Mutex::new(vec![]))
+    }
+
+    // records the files included, even when the compilation fails
+    fn compile_recording(self, included: &Mutex<Vec<(PathBuf, Option<SystemTime>)>>) -> Result<Vec<u8>> {
+        included.lock().unwrap().clear();
+        let (text, name) = match self.source {
+            GlslText::FilePath(filename) => {
+                let text = std::fs::read_to_string(&filename)
+                    .map_err(ErrorCode::ShaderLoadIO)?;
+                (text, filename)
+            },
+            GlslText::Text(text) => (text, TEXT_SOURCE_NAME.to_string()),
+        };
+        let mut compiler = shaderc::Compiler::new()
+            .ok_or_else(|| compile_error("failed to initialize shaderc"))?;
+        let mut options = shaderc::CompileOptions::new()
+            .ok_or_else(|| compile_error("failed to initialize shaderc options"))?;
+        options.set_target_env(shaderc::TargetEnv::Vulkan, self.target_env.env_version() as u32);
+        for (name, value) in self.defines.iter() {
+            options.add_macro_definition(name, value.as_ref().map(|v| v.as_str()));
+        }
+        let include_dirs = self.include_dirs;
+        options.set_include_callback(move |requested, include_type, requester, _| {
+            resolve_include(requested, include_type, requester, &include_dirs, included)
+        });
+        let artifact = compiler.compile_into_spirv(&text, shaderc::ShaderKind::Compute, &name, "main", Some(&options))
+            .map_err(|v| ErrorCode::ShaderCompile(diagnostics(&v)))?;
+        Ok(artifact.as_binary_u8().to_vec())
+    }
+}
+
+impl ShaderSource for GlslSource {
+    fn shader_module(self, context: &Arc<Context>) -> Result<Arc<ShaderModule>> {
+        let bytes = self.compile()?;
+        ShaderModule::new(context.device(), ShaderModuleSource::from_bytes(bytes))
+    }
+
+    fn watch(&self) -> Option<ShaderWatch> {
+        match &self.source {
+            GlslText::FilePath(filename) => {
+                let source = self.clone();
+                let includes = IncludedFiles::default();
+                let included = Arc::clone(&includes);
+                let watch = ShaderWatch::new(filename, move |context| {
+                    let bytes = source.clone().compile_recording(&included)?;
+                    ShaderModule::new(context.device(), ShaderModuleSource::from_bytes(bytes))
+                });
+                Some(watch.with_includes(includes))
+            },
+            GlslText::Text(_) => None,
+        }
+    }
+}
+
+// `#include "..."` is looked up next to the includer first, then in the include directories.
+// the file is recorded with its modification time before it is read
+fn resolve_include(requested: &str,
+    include_type: shaderc::IncludeType,
+    requester: &str,
+    include_dirs: &[PathBuf],
+    included: &Mutex<Vec<(PathBuf, Option<SystemTime>)>>) -> shaderc::IncludeCallbackResult {
+    let relative_dir = match include_type {
+        shaderc::IncludeType::Relative if requester != TEXT_SOURCE_NAME => {
+            Path::new(requester).parent().map(|v| v.to_path_buf())
+        },
+        shaderc::IncludeType::Relative => Some(PathBuf::from(".")),
+        shaderc::IncludeType::Standard => None,
+    };
+    let path = relative_dir.iter()
+        .chain(include_dirs.iter())
+        .map(|v| v.join(requested))
+        .find(|v| v.is_file())
+        .ok_or_else(|| format!("cannot find include file {:?}", requested))?;
+    let modified = std::fs::metadata(&path).and_then(|v| v.modified()).ok();
+    included.lock().unwrap().push((path.clone(), modified));
+    let content = std::fs::read_to_string(&path)
+        .map_err(|v| format!("cannot read include file {:?}: {}", path, v))?;
+    let include = shaderc::ResolvedInclude {
+        resolved_name: path.to_string_lossy().into_owned(),
+        content,
+    };
+    Ok(include)
+}
+
+fn compile_error(message: &str) -> ErrorCode {
+    let diagnostic = ShaderDiagnostic::new(None, None, message.to_string());
+    ErrorCode::ShaderCompile(vec![diagnostic])
+}
+
+// glslang reports each error as `file:line: error: message`
+fn diagnostics(error: &shaderc::Error) -> Vec<ShaderDiagnostic> {
+    let messages = match error {
+        shaderc::Error::CompilationError(_, messages) => messages.clone(),
+        error => return vec![ShaderDiagnostic::new(None, None, error.to_string())],
+    };
+    messages.lines()
+        .filter(|v| !v.trim().is_empty())
+        .map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let file = parts.next();
+            let line_number = parts.next().and_then(|v| v.trim().parse::<u32>().ok());
+            match (file, line_number, parts.next()) {
+                (Some(file), Some(line_number), Some(message)) => {
+                    ShaderDiagnostic::new(Some(file.to_string()), Some(line_number), message.trim().to_string())
+                },
+                _ => ShaderDiagnostic::new(None, None, line.trim().to_string()),
+            }
+        })
+        .collect()
+}
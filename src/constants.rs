@@ -0,0 +1,425 @@
+
+use super::dispatch::{ConstantEntry};
+use super::reflection::{ShaderInterface, ScalarType, BlockLayout};
+use super::half::{f16};
+
+use super::error::Result;
+use super::error::ErrorCode;
+
+// vkCmdPushConstants takes offsets and sizes in multiples of 4
+pub(crate) const PUSH_CONSTANT_ALIGNMENT: usize = 4;
+
+// scalars that can be given to specialization constants and push constants
+pub trait ConstantValue: Copy {
+    fn scalar_type() -> ScalarType;
+    fn to_entry(self) -> ConstantEntry;
+}
+
+macro_rules! impl_constant_value {
+    ($t:ty, $scalar_type:expr) => {
+        impl ConstantValue for $t {
+            fn scalar_type() -> ScalarType {
+                $scalar_type
+            }
+
+            fn to_entry(self) -> ConstantEntry {
+                ConstantEntry::new(self.to_ne_bytes().to_vec())
+            }
+        }
+    };
+}
+
+impl_constant_value!(i32, ScalarType::Int { width: 32, signed: true });
+impl_constant_value!(u32, ScalarType::Int { width: 32, signed: false });
+impl_constant_value!(f32, ScalarType::Float { width: 32 });
+impl_constant_value!(i64, ScalarType::Int { width: 64, signed: true });
+impl_constant_value!(u64, ScalarType::Int { width: 64, signed: false });
+impl_constant_value!(f64, ScalarType::Float { width: 64 });
+
+impl ConstantValue for f16 {
+    fn scalar_type() -> ScalarType {
+        ScalarType::Float { width: 16 }
+    }
+
+    fn to_entry(self) -> ConstantEntry {
+        ConstantEntry::new(self.to_bits().to_ne_bytes().to_vec())
+    }
+}
+
+// booleans are passed as VkBool32
+impl ConstantValue for bool {
+    fn scalar_type() -> ScalarType {
+        ScalarType::Bool
+    }
+
+    fn to_entry(self) -> ConstantEntry {
+        ConstantEntry::new((self as u32).to_ne_bytes().to_vec())
+    }
+}
+
+#[derive(Clone)]
+enum ConstantKey {
+    Id(u32),
+    Name(String),
+}
+
+#[derive(Clone)]
+struct SpecializationEntry {
+    key: ConstantKey,
+    // untyped entries given by `constants!` are checked by size only
+    scalar_type: Option<ScalarType>,
+    entry: ConstantEntry,
+}
+
+// Specialization constants identified by their constant_id or their name
+#[derive(Clone)]
+pub struct Specialization {
+    entries: Vec<SpecializationEntry>,
+}
+
+impl Default for Specialization {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Specialization {
+    pub fn new() -> Self {
+        Specialization { entries: vec![] }
+    }
+
+    pub fn id<T: ConstantValue>(mut self, id: u32, value: T) -> Self {
+        let entry = SpecializationEntry {
+            key: ConstantKey::Id(id),
+            scalar_type: Some(T::scalar_type()),
+            entry: value.to_entry(),
+        };
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn name<T: ConstantValue>(mut self, name: impl Into<String>, value: T) -> Self {
+        let entry = SpecializationEntry {
+            key: ConstantKey::Name(name.into()),
+            scalar_type: Some(T::scalar_type()),
+            entry: value.to_entry(),
+        };
+        self.entries.push(entry);
+        self
+    }
+
+    // the subgroup size given to an int or a uint constant, see `PipelineBuilder::subgroup_size_constant`
+    pub(crate) fn subgroup_size(mut self, id: u32, size: u32) -> Self {
+        let entry = SpecializationEntry {
+            key: ConstantKey::Id(id),
+            scalar_type: None,
+            entry: size.to_entry(),
+        };
+        self.entries.push(entry);
+        self
+    }
+
+    // resolves the entries into constant IDs checking them against the shader
+    pub(crate) fn resolve(self, interface: &ShaderInterface) -> Result<Vec<(u32, ConstantEntry)>> {
+        let mut resolved = Vec::<(u32, ConstantEntry)>::new();
+        for entry in self.entries {
+            let constant = match entry.key {
+                ConstantKey::Id(id) => interface.specialization_constant(id)
+                    .ok_or(ErrorCode::SpecializationConstantNotFound(id))?,
+                ConstantKey::Name(name) => interface.specialization_constant_by_name(&name)
+                    .ok_or(ErrorCode::SpecializationConstantNameNotFound(name))?,
+            };
+            let id = constant.id();
+            let expected = constant.scalar_type();
+            match entry.scalar_type {
+                Some(actual) if actual != expected => {
+                    return Err(ErrorCode::SpecializationConstantTypeMismatch { id, expected, actual }.into())
+                },
+                _ => (),
+            }
+            if entry.entry.size() != expected.size() {
+                let (expected, actual) = (expected.size(), entry.entry.size());
+                return Err(ErrorCode::SpecializationConstantSizeMismatch { id, expected, actual }.into())
+            }
+            // the last one wins when the same constant is given more than once
+            resolved.retain(|(v, _)| *v != id);
+            resolved.push((id, entry.entry));
+        }
+        Ok(resolved)
+    }
+}
+
+// constants! gives specialization constants in the order of their constant_id
+impl From<Vec<ConstantEntry>> for Specialization {
+    fn from(constants: Vec<ConstantEntry>) -> Self {
+        let entries = constants.into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                SpecializationEntry {
+                    key: ConstantKey::Id(index as u32),
+                    scalar_type: None,
+                    entry,
+                }
+            })
+            .collect();
+        Specialization { entries }
+    }
+}
+
+enum PushConstantKey {
+    // placed right after the preceding entry
+    Sequential,
+    Offset(usize),
+    Name(String),
+}
+
+struct PushConstantEntry {
+    key: PushConstantKey,
+    // untyped entries given by `constants!` are checked by size only
+    scalar_type: Option<ScalarType>,
+    entry: ConstantEntry,
+}
+
+// Push constants placed by the member name or the byte offset in the push constant block
+pub struct PushConstants {
+    entries: Vec<PushConstantEntry>,
+}
+
+impl Default for PushConstants {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PushConstants {
+    pub fn new() -> Self {
+        PushConstants { entries: vec![] }
+    }
+
+    pub fn offset<T: ConstantValue>(mut self, offset: usize, value: T) -> Self {
+        let entry = PushConstantEntry {
+            key: PushConstantKey::Offset(offset),
+            scalar_type: Some(T::scalar_type()),
+            entry: value.to_entry(),
+        };
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn name<T: ConstantValue>(mut self, name: impl Into<String>, value: T) -> Self {
+        let entry = PushConstantEntry {
+            key: PushConstantKey::Name(name.into()),
+            scalar_type: Some(T::scalar_type()),
+            entry: value.to_entry(),
+        };
+        self.entries.push(entry);
+        self
+    }
+
+    // lays out the entries into the bytes pushed from offset zero,
+    // `range_size` is the size of the push constant range of the pipeline layout
+    pub(crate) fn resolve(self, interface: &ShaderInterface, range_size: usize) -> Result<Vec<u8>> {
+        let placed = self.place_entries(interface, range_size)?;
+        Ok(Self::pack(&placed))
+    }
+
+    // the entries at their offsets in the push constant block
+    pub(crate) fn place_entries(self, interface: &ShaderInterface, range_size: usize) -> Result<Vec<(usize, ConstantEntry)>> {
+        let block = interface.push_constants();
+        let mut placed = Vec::<(usize, ConstantEntry)>::new();
+        let mut position = 0usize;
+        for entry in self.entries {
+            let offset = match entry.key {
+                PushConstantKey::Sequential => position,
+                PushConstantKey::Offset(offset) => offset,
+                PushConstantKey::Name(name) => Self::member_offset(block, name, entry.entry.size(), entry.scalar_type)?,
+            };
+            position = Self::place(offset, entry.entry.size(), range_size)?;
+            placed.push((offset, entry.entry));
+        }
+        Ok(placed)
+    }
+
+    // the later entries overwrite the earlier ones.
+    // padded to a multiple of 4 bytes as vkCmdPushConstants takes
+    pub(crate) fn pack(placed: &[(usize, ConstantEntry)]) -> Vec<u8> {
+        let mut data = Vec::<u8>::new();
+        for (offset, entry) in placed.iter() {
+            let end = offset + entry.size();
+            if data.len() < end {
+                data.resize(end, 0);
+            }
+            data[*offset..end].copy_from_slice(entry.bytes());
+        }
+        data.resize(data.len().next_multiple_of(PUSH_CONSTANT_ALIGNMENT), 0);
+        data
+    }
+
+    // the offset of the named member taking the value, none when the shader declares no such member
+    pub(crate) fn find<T: ConstantValue>(interface: &ShaderInterface, name: &str, range_size: usize) -> Result<Option<usize>> {
+        let block = interface.push_constants();
+        let is_declared = block
+            .map(|v| v.members().iter().any(|v| v.name() == Some(name)))
+            .unwrap_or(false);
+        if !is_declared {
+            return Ok(None)
+        }
+        let size = T::scalar_type().size();
+        let offset = Self::member_offset(block, name.to_string(), size, Some(T::scalar_type()))?;
+        Self::place(offset, size, range_size)?;
+        Ok(Some(offset))
+    }
+
+    fn member_offset(block: Option<&BlockLayout>, name: String, size: usize, scalar_type: Option<ScalarType>) -> Result<usize> {
+        let member = block
+            .and_then(|v| v.members().iter().find(|v| v.name() == Some(name.as_str())));
+        let member = match member {
+            Some(member) => member,
+            None => return Err(ErrorCode::PushConstantNameNotFound(name).into()),
+        };
+        if member.size() != size {
+            let (expected, actual) = (member.size(), size);
+            return Err(ErrorCode::PushConstantMemberSizeMismatch { name, expected, actual }.into())
+        }
+        match (member.scalar_type(), scalar_type) {
+            (Some(expected), Some(actual)) if actual != expected => {
+                return Err(ErrorCode::PushConstantTypeMismatch { name, expected, actual }.into())
+            },
+            _ => (),
+        }
+        Ok(member.offset())
+    }
+
+    // checks the value fits in the range at the offset, returns where it ends
+    fn place(offset: usize, size: usize, range_size: usize) -> Result<usize> {
+        // scalars are aligned to their own size, 2 bytes for 16-bit and 8 bytes for 64-bit ones
+        let alignment = size.clamp(1, 8);
+        if !offset.is_multiple_of(alignment) {
+            return Err(ErrorCode::PushConstantMisaligned { offset, size }.into())
+        }
+        let end = offset + size;
+        if end > range_size {
+            return Err(ErrorCode::PushConstantSizeMismatch { expected: range_size, actual: end }.into())
+        }
+        Ok(end)
+    }
+}
+
+// constants! gives push constants packed from offset zero
+impl From<Vec<ConstantEntry>> for PushConstants {
+    fn from(constants: Vec<ConstantEntry>) -> Self {
+        let entries = constants.into_iter()
+            .map(|entry| {
+                PushConstantEntry {
+                    key: PushConstantKey::Sequential,
+                    scalar_type: None,
+                    entry,
+                }
+            })
+            .collect();
+        PushConstants { entries }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reflection::tests::named_module;
+
+    fn interface() -> ShaderInterface {
+        ShaderInterface::parse(&named_module()).unwrap()
+    }
+
+    #[test]
+    fn specialization_by_id_and_name() {
+        let resolved = Specialization::new()
+            .id(0, 16u32)
+            .name("SCALE", 0.5f32)
+            .name("WIDTH", 32u32)
+            .resolve(&interface())
+            .unwrap();
+        let resolved: Vec<_> = resolved.iter()
+            .map(|(id, v)| (*id, v.bytes().to_vec()))
+            .collect();
+        // the last one given to WIDTH wins
+        assert_eq!(resolved, [(1, 0.5f32.to_ne_bytes().to_vec()), (0, 32u32.to_ne_bytes().to_vec())]);
+    }
+
+    #[test]
+    fn specialization_errors() {
+        let error = Specialization::new().id(0, 1.0f32).resolve(&interface()).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::SpecializationConstantTypeMismatch { id: 0, .. }));
+        let error = Specialization::new().id(7, 1u32).resolve(&interface()).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::SpecializationConstantNotFound(7)));
+        let error = Specialization::new().name("HEIGHT", 1u32).resolve(&interface()).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::SpecializationConstantNameNotFound(_)));
+        // untyped entries are checked by size only
+        let error = Specialization::from(vec![1u64.to_entry()]).resolve(&interface()).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::SpecializationConstantSizeMismatch { id: 0, expected: 4, actual: 8 }));
+        assert!(Specialization::from(vec![1i32.to_entry()]).resolve(&interface()).is_ok());
+    }
+
+    #[test]
+    fn push_constants_by_name() {
+        let data = PushConstants::new()
+            .name("scale", 2.0f32)
+            .name("count_x", 5u32)
+            .resolve(&interface(), 24)
+            .unwrap();
+        let mut expected = 5u32.to_ne_bytes().to_vec();
+        expected.extend_from_slice(&2.0f32.to_ne_bytes());
+        assert_eq!(data, expected);
+        // a 16-bit member is 2-byte aligned, the data is padded to a multiple of 4
+        let data = PushConstants::new()
+            .name("bias", f16::ONE)
+            .resolve(&interface(), 24)
+            .unwrap();
+        assert_eq!(data.len(), 16);
+        assert_eq!(data[12..14], f16::ONE.to_bits().to_ne_bytes());
+        let data = PushConstants::new()
+            .name("weight", 0.25f64)
+            .resolve(&interface(), 24)
+            .unwrap();
+        assert_eq!(data[16..], 0.25f64.to_ne_bytes());
+    }
+
+    #[test]
+    fn push_constants_errors() {
+        let error = PushConstants::new().name("count_x", 1.0f32).resolve(&interface(), 24).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::PushConstantTypeMismatch { .. }));
+        let error = PushConstants::new().name("bias", 1.0f32).resolve(&interface(), 24).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::PushConstantMemberSizeMismatch { expected: 2, actual: 4, .. }));
+        let error = PushConstants::new().name("count_y", 1u32).resolve(&interface(), 24).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::PushConstantNameNotFound(_)));
+        let error = PushConstants::new().offset(2, 1u32).resolve(&interface(), 24).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::PushConstantMisaligned { offset: 2, size: 4 }));
+        let error = PushConstants::new().offset(4, 1.0f64).resolve(&interface(), 24).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::PushConstantMisaligned { offset: 4, size: 8 }));
+        let error = PushConstants::new().offset(24, 1u32).resolve(&interface(), 24).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::PushConstantSizeMismatch { expected: 24, actual: 28 }));
+    }
+
+    #[test]
+    fn push_constants_in_sequence() {
+        let placed = PushConstants::from(vec![1u32.to_entry(), 2.0f64.to_entry(), f16::ONE.to_entry()])
+            .place_entries(&interface(), 24);
+        // the double right after the uint is misaligned
+        assert!(placed.is_err());
+        let placed = PushConstants::from(vec![1u32.to_entry(), f16::ONE.to_entry(), f16::ZERO.to_entry()])
+            .place_entries(&interface(), 24)
+            .unwrap();
+        let offsets: Vec<usize> = placed.iter().map(|(v, _)| *v).collect();
+        assert_eq!(offsets, [0, 4, 6]);
+        assert_eq!(PushConstants::pack(&placed).len(), 8);
+    }
+
+    #[test]
+    fn find_members() {
+        let interface = interface();
+        assert_eq!(PushConstants::find::<u32>(&interface, "base_x", 24).unwrap(), Some(8));
+        assert_eq!(PushConstants::find::<u32>(&interface, "base_y", 24).unwrap(), None);
+        assert!(PushConstants::find::<f32>(&interface, "base_x", 24).is_err());
+        // the member lies beyond the pushed range
+        assert!(PushConstants::find::<u32>(&interface, "base_x", 8).is_err());
+    }
+}
@@ -1,33 +1,143 @@
 
 use super::instance::{Instance};
-use super::device::{Device, DeviceBuilder, CommandPool};
+use super::device::{Device, DeviceBuilder, CommandPool, ShaderModule, ShaderModuleSource, PipelineCache};
+use super::features::{Feature, SubgroupProperties};
+use super::dispatch::{DescriptorAllocator, PipelineRegistry, PipelineRegistryStats};
+use super::profiler::{Profiler, ProfileRecord, ProfileReport};
 
 use super::error::Result;
+use super::error::ErrorCode;
 use std::sync::Arc;
+use std::path::Path;
+use std::io::ErrorKind;
 
 pub struct Context {
-    instance: Arc<Instance>,
     device: Arc<Device>,
     command_pool: Arc<CommandPool>,
+    pipeline_cache: Arc<PipelineCache>,
+    descriptor_allocator: Arc<DescriptorAllocator>,
+    pipeline_registry: Arc<PipelineRegistry>,
+    // measures GPU durations when the context is built with `ContextBuilder::profiling`
+    profiler: Option<Arc<Profiler>>,
 }
 
 impl Context {
+    // no optional device features enabled, see `ContextBuilder` for them
     pub fn new() -> Result<Arc<Self>> {
+        ContextBuilder::new().build()
+    }
+
+    fn with_features(required: &[Feature], optional: &[Feature], profiling: bool) -> Result<Arc<Self>> {
         let instance = Instance::new()?;
-        let device = DeviceBuilder::new(&instance).build()?;
+        let device = DeviceBuilder::new(&instance)
+            .features(required, optional)
+            .build()?;
         let command_pool = CommandPool::new(&device)?;
+        let pipeline_cache = PipelineCache::new(&device)?;
+        let descriptor_allocator = DescriptorAllocator::new(&device);
+        let pipeline_registry = PipelineRegistry::new();
+        let profiler = if profiling {
+            Some(Profiler::new(&device)?)
+        } else {
+            None
+        };
         log_debug!("GPU: {:?}", device.physical_device().properties().device_name());
         let context = Context { 
-            instance: instance,
             device: device,
             command_pool: command_pool,
+            pipeline_cache,
+            descriptor_allocator,
+            pipeline_registry,
+            profiler,
         };
         Ok(Arc::new(context))
     }
 
+    // whether the feature was enabled on creation
+    pub fn is_feature_enabled(&self, feature: Feature) -> bool {
+        self.device.is_enabled(feature)
+    }
+
+    pub fn enabled_features(&self) -> Vec<Feature> {
+        self.device.enabled_features()
+    }
+
+    // the subgroup size and operations of the device
+    pub fn subgroup_properties(&self) -> &SubgroupProperties {
+        self.device.subgroup_properties()
+    }
+
+    // loads a shader module that pipelines can share, see `PipelineBuilder::shader_module`
+    pub fn shader_module(&self, filename: impl Into<String>) -> Result<Arc<ShaderModule>> {
+        ShaderModule::new(&self.device, ShaderModuleSource::from_file(filename))
+    }
+
+    pub fn shader_module_bytes(&self, bytes: Vec<u8>) -> Result<Arc<ShaderModule>> {
+        ShaderModule::new(&self.device, ShaderModuleSource::from_bytes(bytes))
+    }
+
+    pub fn shader_module_words(&self, words: &[u32]) -> Result<Arc<ShaderModule>> {
+        ShaderModule::from_words(&self.device, words)
+    }
+
+    // writes the pipelines compiled so far so that the next run can skip compiling them
+    pub fn save_pipeline_cache(&self, path: impl AsRef<Path>) -> Result<()> {
+        let data = self.pipeline_cache.data()?;
+        std::fs::write(path, data)
+            .map_err(|v| ErrorCode::PipelineCacheIO(v).into())
+    }
+
+    // loads a cache written by `save_pipeline_cache`, pipelines built afterwards make use of it.
+    // returns false when the file is missing or it was made by another device or driver
+    pub fn load_pipeline_cache(&self, path: impl AsRef<Path>) -> Result<bool> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(ref error) if error.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(ErrorCode::PipelineCacheIO(error).into()),
+        };
+        let is_loaded = self.pipeline_cache.merge_data(&data)?;
+        if !is_loaded {
+            log_debug!("Discarded stale pipeline cache");
+        }
+        Ok(is_loaded)
+    }
+
+    // how many pipelines built have shared one compiled before.
+    // pipelines built from the same code, entry point, specialization constants and layout are shared
+    pub fn pipeline_stats(&self) -> PipelineRegistryStats {
+        self.pipeline_registry.stats()
+    }
+
+    // drops the compiled pipelines no pipeline uses, returns how many.
+    // only the least recently used are dropped otherwise once there are too many
+    pub fn evict_unused_pipelines(&self) -> usize {
+        self.pipeline_registry.evict_unused()
+    }
+
     #[inline]
-    pub(crate) fn instance(&self) -> &Arc<Instance> {
-        &self.instance
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    // the GPU durations of the latest dispatches and transfers, the oldest first.
+    // empty unless the context is built with `ContextBuilder::profiling`
+    pub fn profile_records(&self) -> Vec<ProfileRecord> {
+        self.profiler.as_ref()
+            .map(|v| v.records())
+            .unwrap_or_default()
+    }
+
+    // the GPU time spent on each pipeline and on the transfers so far
+    pub fn profile_report(&self) -> ProfileReport {
+        self.profiler.as_ref()
+            .map(|v| v.report())
+            .unwrap_or_default()
+    }
+
+    pub fn reset_profile(&self) {
+        if let Some(profiler) = self.profiler.as_ref() {
+            profiler.reset();
+        }
     }
 
     #[inline]
@@ -39,4 +149,70 @@ impl Context {
     pub(crate) fn command_pool(&self) -> &Arc<CommandPool> {
         &self.command_pool
     }
+
+    #[inline]
+    pub(crate) fn pipeline_cache(&self) -> &Arc<PipelineCache> {
+        &self.pipeline_cache
+    }
+
+    #[inline]
+    pub(crate) fn descriptor_allocator(&self) -> &Arc<DescriptorAllocator> {
+        &self.descriptor_allocator
+    }
+
+    #[inline]
+    pub(crate) fn pipeline_registry(&self) -> &Arc<PipelineRegistry> {
+        &self.pipeline_registry
+    }
+
+    #[inline]
+    pub(crate) fn profiler(&self) -> Option<&Arc<Profiler>> {
+        self.profiler.as_ref()
+    }
+}
+
+// Context with device features enabled for shaders depending on them
+pub struct ContextBuilder {
+    required_features: Vec<Feature>,
+    optional_features: Vec<Feature>,
+    profiling: bool,
+}
+
+impl Default for ContextBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextBuilder {
+    pub fn new() -> Self {
+        ContextBuilder {
+            required_features: vec![],
+            optional_features: vec![],
+            profiling: false,
+        }
+    }
+
+    // fails to build unless a device supports the feature
+    pub fn require(mut self, feature: Feature) -> Self {
+        self.required_features.push(feature);
+        self
+    }
+
+    // enabled if the device supports the feature, see `Context::is_feature_enabled`
+    pub fn request(mut self, feature: Feature) -> Self {
+        self.optional_features.push(feature);
+        self
+    }
+
+    // writes timestamps around dispatches and transfers, see `Context::profile_report`.
+    // fails to build unless the compute queue supports timestamps
+    pub fn profiling(mut self) -> Self {
+        self.profiling = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Arc<Context>> {
+        Context::with_features(&self.required_features, &self.optional_features, self.profiling)
+    }
 }
@@ -3,18 +3,23 @@ use super::vk::*;
 use super::error::Result;
 use super::error::ErrorCode;
 use super::instance::{Instance, QueueFamily, PhysicalDevice, PhysicalDevicesBuilder};
+use super::reflection::*;
+use super::features::{Feature, FeatureSet, SubgroupProperties};
 
 use std::ptr;
-use std::mem;
 use std::mem::MaybeUninit;
 use libc::{c_float, c_void};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard};
 use std::io::Read;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
 
 pub struct Device {
     handle: VkDevice,
     queue: Queue,
     physical_device: Arc<PhysicalDevice>,
+    features: FeatureSet,
+    subgroup_properties: SubgroupProperties,
 }
 
 impl Device {
@@ -32,8 +37,92 @@ impl Device {
     pub fn physical_device(&self) -> &Arc<PhysicalDevice> {
         &self.physical_device
     }
+
+    // the core features enabled on creation
+    #[inline]
+    pub fn features(&self) -> &VkPhysicalDeviceFeatures {
+        self.features.core()
+    }
+
+    #[inline]
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.features.contains(feature)
+    }
+
+    pub fn enabled_features(&self) -> Vec<Feature> {
+        self.features.features()
+    }
+
+    #[inline]
+    pub fn subgroup_properties(&self) -> &SubgroupProperties {
+        &self.subgroup_properties
+    }
+
+    // the latest SPIR-V version the device accepts as (major, minor),
+    // limited by the Vulkan version the instance requests
+    pub fn spirv_version(&self) -> (u32, u32) {
+        let api_version = self.physical_device.properties().apiVersion.min(INSTANCE_API_VERSION);
+        match (vk_version_major(api_version), vk_version_minor(api_version)) {
+            (1, 0) => (1, 0),
+            (1, 1) => (1, 3),
+            (1, 2) => (1, 5),
+            _ => (1, 6),
+        }
+    }
+
+    // checks what the module requires against the device before handing it to the driver
+    pub(crate) fn validate_shader(&self, interface: &ShaderInterface) -> Result<()> {
+        let supported = self.spirv_version();
+        let version = interface.version();
+        if version > supported {
+            return Err(ErrorCode::ShaderVersionUnsupported { version, supported }.into())
+        }
+        let capability = interface.capabilities().iter()
+            .find(|&&v| !self.capability_enabled(v));
+        match capability {
+            Some(&capability) => Err(ErrorCode::ShaderCapabilityUnsupported(capability).into()),
+            None => Ok(()),
+        }
+    }
+
+    // capabilities not listed here are left to the driver
+    fn capability_enabled(&self, capability: u32) -> bool {
+        let enabled = |feature| self.is_enabled(feature);
+        match capability {
+            CAPABILITY_MATRIX | CAPABILITY_SHADER => true,
+            CAPABILITY_FLOAT64 => enabled(Feature::ShaderFloat64),
+            CAPABILITY_INT64 => enabled(Feature::ShaderInt64),
+            CAPABILITY_INT16 => enabled(Feature::ShaderInt16),
+            CAPABILITY_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING => enabled(Feature::ShaderStorageBufferArrayDynamicIndexing),
+            CAPABILITY_STORAGE_IMAGE_EXTENDED_FORMATS => enabled(Feature::ShaderStorageImageExtendedFormats),
+            CAPABILITY_STORAGE_IMAGE_READ_WITHOUT_FORMAT => enabled(Feature::ShaderStorageImageReadWithoutFormat),
+            CAPABILITY_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT => enabled(Feature::ShaderStorageImageWriteWithoutFormat),
+            CAPABILITY_STORAGE_BUFFER_16BIT_ACCESS => enabled(Feature::StorageBuffer16BitAccess),
+            CAPABILITY_UNIFORM_AND_STORAGE_BUFFER_16BIT_ACCESS => enabled(Feature::UniformAndStorageBuffer16BitAccess),
+            CAPABILITY_STORAGE_PUSH_CONSTANT16 => enabled(Feature::StoragePushConstant16),
+            CAPABILITY_VARIABLE_POINTERS_STORAGE_BUFFER => enabled(Feature::VariablePointersStorageBuffer),
+            CAPABILITY_VARIABLE_POINTERS => enabled(Feature::VariablePointers),
+            CAPABILITY_FLOAT16 => enabled(Feature::ShaderFloat16),
+            CAPABILITY_INT8 => enabled(Feature::ShaderInt8),
+            CAPABILITY_INT64_ATOMICS => enabled(Feature::ShaderBufferInt64Atomics) || enabled(Feature::ShaderSharedInt64Atomics),
+            CAPABILITY_STORAGE_BUFFER_8BIT_ACCESS => enabled(Feature::StorageBuffer8BitAccess),
+            CAPABILITY_UNIFORM_AND_STORAGE_BUFFER_8BIT_ACCESS => enabled(Feature::UniformAndStorageBuffer8BitAccess),
+            CAPABILITY_STORAGE_PUSH_CONSTANT8 => enabled(Feature::StoragePushConstant8),
+            CAPABILITY_RUNTIME_DESCRIPTOR_ARRAY => enabled(Feature::RuntimeDescriptorArray),
+            CAPABILITY_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING => enabled(Feature::ShaderStorageBufferArrayNonUniformIndexing),
+            CAPABILITY_VULKAN_MEMORY_MODEL => enabled(Feature::VulkanMemoryModel),
+            CAPABILITY_PHYSICAL_STORAGE_BUFFER_ADDRESSES => enabled(Feature::BufferDeviceAddress),
+            // comes with Vulkan 1.2 itself
+            CAPABILITY_SHADER_NON_UNIFORM => self.physical_device.properties().apiVersion >= VK_API_VERSION_1_2,
+            _ => true,
+        }
+    }
 }
 
+// the handle is only externally synchronized on destruction, which Drop makes exclusive
+unsafe impl Send for Device {}
+unsafe impl Sync for Device {}
+
 impl Drop for Device {
     fn drop(&mut self) {
         log_debug!("Drop Device");
@@ -48,7 +137,7 @@ pub struct BufferMemory {
     buffer: VkBuffer,
     memory: VkDeviceMemory,
     device: Arc<Device>,
-    whole_size: VkDeviceSize,
+    usage: VkBufferUsageFlags,
 }
 
 impl BufferMemory {
@@ -84,7 +173,12 @@ impl BufferMemory {
                 .unwrap();
             // allocation
             let mut memory = MaybeUninit::<VkDeviceMemory>::zeroed();
-            let allocate_info = VkMemoryAllocateInfo::new(requirements.size, memory_type_index);
+            let mut allocate_info = VkMemoryAllocateInfo::new(requirements.size, memory_type_index);
+            // buffers whose address shaders take need the memory allocated for it
+            let allocate_flags_info = VkMemoryAllocateFlagsInfo::new(VkMemoryAllocateFlagBits::VK_MEMORY_ALLOCATE_DEVICE_ADDRESS_BIT as u32);
+            if (usage & VkBufferUsageFlagBits::VK_BUFFER_USAGE_SHADER_DEVICE_ADDRESS_BIT as u32) != 0 {
+                allocate_info.pNext = &allocate_flags_info as *const _ as *const c_void;
+            }
             vkAllocateMemory(device.handle(), &allocate_info, ptr::null(), memory.as_mut_ptr())
                 .into_result()
                 .unwrap();
@@ -97,7 +191,7 @@ impl BufferMemory {
                 buffer: buffer,
                 memory: memory,
                 device: Arc::clone(device),
-                whole_size: size,
+                usage,
             };
             Ok(Arc::new(buffer_memory))
         }
@@ -117,8 +211,25 @@ impl BufferMemory {
     pub fn device(&self) -> &Arc<Device> {
         &self.device
     }
+
+    // the address shaders reach the buffer at through `GL_EXT_buffer_reference`,
+    // only for buffers created with VK_BUFFER_USAGE_SHADER_DEVICE_ADDRESS_BIT
+    pub fn device_address(&self) -> Result<VkDeviceAddress> {
+        if (self.usage & VkBufferUsageFlagBits::VK_BUFFER_USAGE_SHADER_DEVICE_ADDRESS_BIT as u32) == 0 {
+            return Err(ErrorCode::FeatureNotEnabled(Feature::BufferDeviceAddress).into())
+        }
+        let info = VkBufferDeviceAddressInfo::new(self.buffer);
+        let address = unsafe {
+            vkGetBufferDeviceAddress(self.device.handle(), &info)
+        };
+        Ok(address)
+    }
 }
 
+// the buffer and the memory are only bound once on creation
+unsafe impl Send for BufferMemory {}
+unsafe impl Sync for BufferMemory {}
+
 impl Drop for BufferMemory {
     fn drop(&mut self) {
         unsafe {
@@ -134,6 +245,7 @@ impl Drop for BufferMemory {
 pub struct CommandPool {
     handle: VkCommandPool,
     device: Arc<Device>,
+    lock: Mutex<()>,
 }
 
 impl CommandPool {
@@ -147,6 +259,7 @@ impl CommandPool {
             let command_pool = CommandPool {
                 handle: handle,
                 device: Arc::clone(device),
+                lock: Mutex::new(()),
             };
             Ok(Arc::new(command_pool))
         }
@@ -161,8 +274,17 @@ impl CommandPool {
     pub fn device(&self) -> &Arc<Device> {
         &self.device
     }
+
+    // the pool is externally synchronized, so is allocating, freeing and recording command buffers from it
+    pub fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap()
+    }
 }
 
+// every use of the handle is guarded by `lock`
+unsafe impl Send for CommandPool {}
+unsafe impl Sync for CommandPool {}
+
 impl Drop for CommandPool {
     fn drop(&mut self) {
         log_debug!("Drop CommandPool");
@@ -173,14 +295,189 @@ impl Drop for CommandPool {
     }
 }
 
+pub struct PipelineCache {
+    handle: VkPipelineCache,
+    device: Arc<Device>,
+    // merging needs exclusive access while pipelines can be created concurrently
+    lock: RwLock<()>,
+}
+
+impl PipelineCache {
+    pub fn new(device: &Arc<Device>) -> Result<Arc<Self>> {
+        let handle = Self::create(device, &[])?;
+        let pipeline_cache = PipelineCache {
+            handle,
+            device: Arc::clone(device),
+            lock: RwLock::new(()),
+        };
+        Ok(Arc::new(pipeline_cache))
+    }
+
+    fn create(device: &Arc<Device>, data: &[u8]) -> Result<VkPipelineCache> {
+        unsafe {
+            let mut handle = MaybeUninit::<VkPipelineCache>::zeroed();
+            let create_info = VkPipelineCacheCreateInfo::new(data.len(), data.as_ptr() as *const c_void);
+            vkCreatePipelineCache(device.handle, &create_info, ptr::null(), handle.as_mut_ptr())
+                .into_result()?;
+            Ok(handle.assume_init())
+        }
+    }
+
+    #[inline]
+    pub fn handle(&self) -> VkPipelineCache {
+        self.handle
+    }
+
+    // held while creating pipelines with the cache
+    pub fn read(&self) -> RwLockReadGuard<'_, ()> {
+        self.lock.read().unwrap()
+    }
+
+    pub fn data(&self) -> Result<Vec<u8>> {
+        let _guard = self.lock.write().unwrap();
+        unsafe {
+            let mut size: usize = 0;
+            vkGetPipelineCacheData(self.device.handle, self.handle, &mut size, ptr::null_mut())
+                .into_result()?;
+            let mut data = vec![0u8; size];
+            vkGetPipelineCacheData(self.device.handle, self.handle, &mut size, data.as_mut_ptr() as *mut c_void)
+                .into_result()?;
+            data.truncate(size);
+            Ok(data)
+        }
+    }
+
+    // merges the data obtained by `data` before,
+    // returns false leaving the cache as it is when the data was made by another device or driver
+    pub fn merge_data(&self, data: &[u8]) -> Result<bool> {
+        let properties = self.device.physical_device().properties();
+        if !is_pipeline_cache_compatible(&properties, data) {
+            return Ok(false)
+        }
+        let source = Self::create(&self.device, data)?;
+        let _guard = self.lock.write().unwrap();
+        unsafe {
+            let result = vkMergePipelineCaches(self.device.handle, self.handle, 1, &source)
+                .into_result();
+            vkDestroyPipelineCache(self.device.handle, source, ptr::null());
+            result?;
+        }
+        Ok(true)
+    }
+}
+
+// the header is laid out as VkPipelineCacheHeaderVersionOne in little-endian
+// @see https://www.khronos.org/registry/vulkan/specs/1.1-extensions/html/vkspec.html#pipelines-cache-header
+fn is_pipeline_cache_compatible(properties: &VkPhysicalDeviceProperties, data: &[u8]) -> bool {
+    let header_size = 16 + VK_UUID_SIZE;
+    if data.len() < header_size {
+        return false
+    }
+    let word = |index: usize| {
+        let offset = index * 4;
+        u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+    };
+    word(0) as usize >= header_size 
+        && word(1) == VK_PIPELINE_CACHE_HEADER_VERSION_ONE
+        && word(2) == properties.vendorID
+        && word(3) == properties.deviceID
+        && data[16..header_size] == properties.pipelineCacheUUID[..]
+}
+
+// pipelines may be created concurrently, merging is guarded by `lock`
+unsafe impl Send for PipelineCache {}
+unsafe impl Sync for PipelineCache {}
+
+impl Drop for PipelineCache {
+    fn drop(&mut self) {
+        log_debug!("Drop PipelineCache");
+        unsafe {
+            vkDestroyPipelineCache(self.device.handle, self.handle, ptr::null());
+            self.handle = ptr::null_mut();
+        }
+    }
+}
+
+pub struct Fence {
+    handle: VkFence,
+    device: Arc<Device>,
+}
+
+impl Fence {
+    pub fn new(device: &Arc<Device>, signaled: bool) -> Result<Arc<Self>> {
+        let flags = if signaled {
+            VkFenceCreateFlagBits::VK_FENCE_CREATE_SIGNALED_BIT as VkFlags
+        } else {
+            VK_FLAGS_NONE
+        };
+        unsafe {
+            let mut handle = MaybeUninit::<VkFence>::zeroed();
+            let create_info = VkFenceCreateInfo::new(flags);
+            vkCreateFence(device.handle, &create_info, ptr::null(), handle.as_mut_ptr())
+                .into_result()?;
+            let fence = Fence {
+                handle: handle.assume_init(),
+                device: Arc::clone(device),
+            };
+            Ok(Arc::new(fence))
+        }
+    }
+
+    #[inline]
+    pub fn handle(&self) -> VkFence {
+        self.handle
+    }
+
+    pub fn wait(&self) {
+        unsafe {
+            vkWaitForFences(self.device.handle, 1, &self.handle, VK_TRUE, u64::MAX)
+                .into_result()
+                .unwrap();
+        }
+    }
+
+    // the fence is externally synchronized, the caller must ensure nobody else resets or submits it
+    pub fn reset(&self) {
+        unsafe {
+            vkResetFences(self.device.handle, 1, &self.handle)
+                .into_result()
+                .unwrap();
+        }
+    }
+}
+
+// waiting is not externally synchronized, resetting is left to the owner
+unsafe impl Send for Fence {}
+unsafe impl Sync for Fence {}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        log_debug!("Drop Fence");
+        unsafe {
+            vkDestroyFence(self.device.handle, self.handle, ptr::null());
+            self.handle = ptr::null_mut();
+        }
+    }
+}
+
 pub struct Queue {
     handle: VkQueue,
     family: QueueFamily,
+    lock: Mutex<()>,
 }
 
 impl Queue {
     fn new(handle: VkQueue, family: QueueFamily) -> Self {
-        Queue { handle: handle, family: family }
+        Queue { handle, family, lock: Mutex::new(()) }
+    }
+
+    // vkQueueSubmit requires the queue to be externally synchronized
+    pub fn submit(&self, submit_info: &VkSubmitInfo, fence: VkFence) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        unsafe {
+            vkQueueSubmit(self.handle, 1, submit_info, fence)
+                .into_result()
+        }
     }
 
     #[inline]
@@ -196,18 +493,49 @@ impl Queue {
 
 pub struct DeviceBuilder<'a> {
     instance: &'a Arc<Instance>,
+    required_features: Vec<Feature>,
+    optional_features: Vec<Feature>,
 }
 
 impl<'a> DeviceBuilder<'a> {
     pub fn new(instance: &'a Arc<Instance>) -> Self {
-        DeviceBuilder { instance }
+        DeviceBuilder { 
+            instance,
+            required_features: vec![],
+            optional_features: vec![],
+        }
+    }
+
+    // devices lacking any of the required features are skipped,
+    // the optional ones are enabled as far as the device supports them
+    pub fn features(mut self, required: &[Feature], optional: &[Feature]) -> Self {
+        self.required_features.extend_from_slice(required);
+        self.optional_features.extend_from_slice(optional);
+        self
     }
 
     pub fn build(self) -> Result<Arc<Device>> {
         let devices = PhysicalDevicesBuilder::new(self.instance).build()?;
+        let required = &self.required_features;
+        // the first device supporting the required features,
+        // or the first missing feature of the first device to be reported
+        let mut missing: Option<Feature> = None;
         let device = devices.into_iter()
-            .nth(0)
-            .ok_or_else(|| ErrorCode::SuitablePhysicalDeviceNotFound)?;
+            .map(|device| {
+                let supported = FeatureSet::supported(&device);
+                (device, supported)
+            })
+            .find(|(_, supported)| {
+                let feature = required.iter().find(|&&v| !supported.contains(v));
+                missing = missing.or(feature.cloned());
+                feature.is_none()
+            });
+        let (device, supported) = match (device, missing) {
+            (Some(device), _) => device,
+            (None, Some(feature)) => return Err(ErrorCode::FeatureUnsupported(feature).into()),
+            (None, None) => return Err(ErrorCode::SuitablePhysicalDeviceNotFound.into()),
+        };
+        let mut features = supported.subset(self.required_features.iter().chain(self.optional_features.iter()));
         let families = device.queue_families()?;
         // iterate through compute family candidates keeping the indices
         let compute_families: Vec<_> = families.into_iter()
@@ -220,7 +548,15 @@ impl<'a> DeviceBuilder<'a> {
         let family_index = family.index() as u32;
         let priority: c_float = 0.0;
         let queue_create_info = VkDeviceQueueCreateInfo::new(family_index, 1, &priority);
-        let device_create_info = VkDeviceCreateInfo::new(1, &queue_create_info);
+        let is_chained = FeatureSet::is_chained(&device);
+        let features_chain = features.link();
+        let extension_names = features.extension_names();
+        let device_create_info = if is_chained {
+            VkDeviceCreateInfo::with_features_chain(1, &queue_create_info, &features_chain, 
+                extension_names.len() as u32, extension_names.as_ptr())
+        } else {
+            VkDeviceCreateInfo::new(1, &queue_create_info, features.core())
+        };
         unsafe {
             let mut handle = MaybeUninit::<VkDevice>::zeroed();
             vkCreateDevice(device.handle(), &device_create_info, std::ptr::null(), handle.as_mut_ptr())
@@ -230,10 +566,14 @@ impl<'a> DeviceBuilder<'a> {
             let mut queue = MaybeUninit::<VkQueue>::zeroed();
             vkGetDeviceQueue(handle, family_index, 0, queue.as_mut_ptr());
             let queue = Queue::new(queue.assume_init(), family);
+            features.unlink();
+            let subgroup_properties = SubgroupProperties::query(&device);
             let device = Device {
                 handle: handle,
                 queue: queue,
                 physical_device: device,
+                features,
+                subgroup_properties,
             };
             Ok(Arc::new(device))
         }
@@ -283,20 +623,38 @@ impl ShaderModuleSource {
 pub struct ShaderModule {
     handle: VkShaderModule,
     device: Arc<Device>,
+    interface: ShaderInterface,
+    // pipelines built from the same code are shared
+    code: ShaderCode,
 }
 
 impl ShaderModule {
     pub fn new(device: &Arc<Device>, source: ShaderModuleSource) -> Result<Arc<Self>> {
+        let (buffer, _) = source.load()?;
+        let words: Vec<u32> = buffer.chunks_exact(4)
+            .map(|v| u32::from_ne_bytes([v[0], v[1], v[2], v[3]]))
+            .collect();
+        Self::from_words(device, &words)
+    }
+
+    // creates the module right from the words, e.g. embedded by `include_spirv!`
+    pub fn from_words(device: &Arc<Device>, words: &[u32]) -> Result<Arc<Self>> {
         unsafe {
-            let (buffer, num_bytes) = source.load()?;
+            if words.is_empty() {
+                return Err(ErrorCode::ShaderLoadUnaligned.into())
+            }
+            let interface = ShaderInterface::parse(words)?;
+            device.validate_shader(&interface)?;
             let mut handle = MaybeUninit::<VkShaderModule>::zeroed();
-            let create_info = VkShaderModuleCreateInfo::new(num_bytes, buffer.as_ptr() as *const u32);
+            let create_info = VkShaderModuleCreateInfo::new(words.len() * 4, words.as_ptr());
             vkCreateShaderModule(device.handle, &create_info, ptr::null(), handle.as_mut_ptr())
                 .into_result()?;
             let handle = handle.assume_init();
             let shader_module = ShaderModule {
                 handle: handle,
                 device: Arc::clone(device),
+                interface,
+                code: ShaderCode::new(words),
             };
             Ok(Arc::new(shader_module))
         }
@@ -306,8 +664,55 @@ impl ShaderModule {
     pub fn handle(&self) -> VkShaderModule {
         self.handle
     }
+
+    #[inline]
+    pub fn interface(&self) -> &ShaderInterface {
+        &self.interface
+    }
+
+    #[inline]
+    pub(crate) fn code(&self) -> &ShaderCode {
+        &self.code
+    }
+}
+
+// the words of a module, hashed once and compared on a hash match
+#[derive(Clone)]
+pub(crate) struct ShaderCode {
+    hash: u64,
+    words: Arc<[u32]>,
+}
+
+impl ShaderCode {
+    fn new(words: &[u32]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        words.hash(&mut hasher);
+        ShaderCode {
+            hash: hasher.finish(),
+            words: Arc::from(words),
+        }
+    }
 }
 
+impl PartialEq for ShaderCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash &&
+            (Arc::ptr_eq(&self.words, &other.words) || self.words == other.words)
+    }
+}
+
+impl Eq for ShaderCode {}
+
+impl Hash for ShaderCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+// shader modules are immutable once created
+unsafe impl Send for ShaderModule {}
+unsafe impl Sync for ShaderModule {}
+
 impl Drop for ShaderModule {
     fn drop(&mut self) {
         log_debug!("Drop ShaderModule");
@@ -3,26 +3,57 @@
 use crate::vk::*;
 use crate::error::Result;
 use crate::error::ErrorCode;
-use super::device::{Device, ShaderModule, CommandPool, BufferMemory};
+use super::device::{Device, ShaderModule, ShaderCode, CommandPool, BufferMemory, Fence, PipelineCache};
+use super::features::{Feature};
+use super::buffer::{Buffer};
+use super::profiler::{Profiler, TimestampQuery, ProfiledOperation};
 
 use std::ptr;
 use std::mem;
-use std::ffi::{CStr, CString};
+use std::ffi::{CString};
 use std::mem::MaybeUninit;
 use libc::{c_void};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, Condvar, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::time::Duration;
+use std::convert::TryFrom;
 
 pub struct CommandDispatch {
     compute_pipeline: Arc<ComputePipeline>,
+    // the sets bound by the dispatch in the order of set numbers
+    binding_sets: Vec<Arc<BindingSet>>,
+    // the distinct buffers of the binding sets
+    staging_buffers: Vec<Arc<StagingBuffer>>,
     command_buffer: VkCommandBuffer,
-    fence: VkFence,
+    fence: Arc<Fence>,
+    // written around the dispatch when the context is profiling
+    timestamps: Option<TimestampQuery>,
+    // a command buffer must not be submitted again while it is pending
+    lock: Mutex<()>,
 }
 
 impl CommandDispatch {
-    pub fn new(compute_pipeline: &Arc<ComputePipeline>, workgroup_count: WorkgroupCount, push_constants: Vec<ConstantEntry>) -> Arc<Self> {
-        let staging_buffer = compute_pipeline.staging_buffer();
+    // `binding_sets` are in the order of set numbers, starting from set 0.
+    // `dynamic_offsets` are in the order of the sets and then of the dynamic bindings in each
+    pub fn new(compute_pipeline: &Arc<ComputePipeline>, 
+        command: impl Into<DispatchCommand>, 
+        push_constants: Vec<u8>, 
+        binding_sets: Vec<Arc<BindingSet>>,
+        dynamic_offsets: &[u32]) -> Arc<Self> {
+        let command = command.into();
+        let mut staging_buffers: Vec<Arc<StagingBuffer>> = vec![];
+        for binding_set in binding_sets.iter() {
+            let staging_buffer = binding_set.staging_buffer();
+            if !staging_buffers.iter().any(|v| Arc::ptr_eq(v, staging_buffer)) {
+                staging_buffers.push(Arc::clone(staging_buffer));
+            }
+        }
+        // indirect workgroup counts are read from the buffer of set 0
+        let staging_buffer = Arc::clone(&staging_buffers[0]);
         let command_pool = staging_buffer.command_pool();
         let device = command_pool.device();
+        let _guard = command_pool.lock();
         unsafe {
             let mut command_buffer = MaybeUninit::<VkCommandBuffer>::zeroed();
             {
@@ -32,31 +63,58 @@ impl CommandDispatch {
                     .unwrap();
             }
             let command_buffer = command_buffer.assume_init();
-            let mut fence = MaybeUninit::<VkFence>::zeroed();
-            {
-                let create_info = VkFenceCreateInfo::new(VkFenceCreateFlagBits::VK_FENCE_CREATE_SIGNALED_BIT as VkFlags);
-                vkCreateFence(device.handle(), &create_info, ptr::null(), fence.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
-            }
-            let fence = fence.assume_init();
+            let fence = Fence::new(device, true)
+                .unwrap();
+            let timestamps = staging_buffer.profiler()
+                .map(|v| {
+                    let operation = ProfiledOperation::Dispatch {
+                        pipeline: compute_pipeline.id(),
+                        entry_point: compute_pipeline.entry_point().to_string(),
+                    };
+                    TimestampQuery::new(v, operation)
+                });
             let begin_info = VkCommandBufferBeginInfo::new();
             vkBeginCommandBuffer(command_buffer, &begin_info)
                 .into_result()
                 .unwrap();
             // Barrier to ensure that input buffer transfer is finished before compute shader reads from it
             {
+                let buffer_barriers = staging_buffers.iter()
+                    .map(|v| {
+                        VkBufferMemoryBarrier::new(
+                            VkAccessFlagBits::VK_ACCESS_HOST_WRITE_BIT as VkFlags,
+                            VkAccessFlagBits::VK_ACCESS_SHADER_READ_BIT as VkFlags,
+                            v.device_buffer_memory().buffer(),
+                            0,
+                            VK_WHOLE_SIZE,
+                        )
+                    })
+                    .collect::<Vec<VkBufferMemoryBarrier>>();
+                vkCmdPipelineBarrier(
+                    command_buffer,
+                    VkPipelineStageFlagBits::VK_PIPELINE_STAGE_HOST_BIT as VkFlags,
+                    VkPipelineStageFlagBits::VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT as VkFlags,
+                    VK_FLAGS_NONE,
+                    0, ptr::null(),
+                    buffer_barriers.len() as u32, buffer_barriers.as_ptr(),
+                    0, ptr::null(),
+                );
+            }
+            // Barrier to ensure that the workgroup counts are written before they are read
+            if let DispatchCommand::Indirect(_) = command {
                 let buffer_barrier = VkBufferMemoryBarrier::new(
-                    VkAccessFlagBits::VK_ACCESS_HOST_WRITE_BIT as VkFlags,
-                    VkAccessFlagBits::VK_ACCESS_SHADER_READ_BIT as VkFlags,
+                    VkAccessFlagBits::VK_ACCESS_SHADER_WRITE_BIT as VkFlags |
+                        VkAccessFlagBits::VK_ACCESS_TRANSFER_WRITE_BIT as VkFlags,
+                    VkAccessFlagBits::VK_ACCESS_INDIRECT_COMMAND_READ_BIT as VkFlags,
                     staging_buffer.device_buffer_memory().buffer(),
                     0,
                     VK_WHOLE_SIZE,
                 );
                 vkCmdPipelineBarrier(
                     command_buffer,
-                    VkPipelineStageFlagBits::VK_PIPELINE_STAGE_HOST_BIT as VkFlags,
-                    VkPipelineStageFlagBits::VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT as VkFlags,
+                    VkPipelineStageFlagBits::VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT as VkFlags |
+                        VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TRANSFER_BIT as VkFlags,
+                    VkPipelineStageFlagBits::VK_PIPELINE_STAGE_DRAW_INDIRECT_BIT as VkFlags,
                     VK_FLAGS_NONE,
                     0, ptr::null(),
                     1, &buffer_barrier,
@@ -64,9 +122,7 @@ impl CommandDispatch {
                 );
             }
             if !push_constants.is_empty() {
-                let data = push_constants.iter()
-                    .flat_map(|entry| entry.bytes.iter().cloned())
-                    .collect::<Vec<u8>>();
+                let data = push_constants;
                 vkCmdPushConstants(
                     command_buffer,
                     compute_pipeline.layout,
@@ -80,27 +136,63 @@ impl CommandDispatch {
                 command_buffer,
                 VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_COMPUTE,
                 compute_pipeline.handle);
+            let descriptor_sets = binding_sets.iter()
+                .map(|v| v.descriptor_set)
+                .collect::<Vec<VkDescriptorSet>>();
             vkCmdBindDescriptorSets(
                 command_buffer,
                 VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_COMPUTE,
                 compute_pipeline.layout,
                 0,
-                1,
-                &compute_pipeline.descriptor_set,
-                0,
-                ptr::null()
+                descriptor_sets.len() as u32,
+                descriptor_sets.as_ptr(),
+                dynamic_offsets.len() as u32,
+                dynamic_offsets.as_ptr()
             );
-            vkCmdDispatch(command_buffer, workgroup_count.x, workgroup_count.y, workgroup_count.z);
+            if let Some(timestamps) = timestamps.as_ref() {
+                timestamps.record_begin(command_buffer);
+            }
+            match command {
+                DispatchCommand::Direct(workgroup_count) => {
+                    vkCmdDispatch(command_buffer, workgroup_count.x, workgroup_count.y, workgroup_count.z);
+                },
+                DispatchCommand::Base(base, workgroup_count) => {
+                    vkCmdDispatchBase(command_buffer, base.x, base.y, base.z, workgroup_count.x, workgroup_count.y, workgroup_count.z);
+                },
+                DispatchCommand::Indirect(offset) => {
+                    vkCmdDispatchIndirect(command_buffer, staging_buffer.device_buffer_memory().buffer(), offset);
+                },
+                DispatchCommand::Split { parts, offset } => {
+                    for (value, workgroup_count) in parts.iter() {
+                        vkCmdPushConstants(
+                            command_buffer,
+                            compute_pipeline.layout,
+                            VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32,
+                            offset,
+                            mem::size_of::<u32>() as u32,
+                            value as *const u32 as *const c_void,
+                        );
+                        vkCmdDispatch(command_buffer, workgroup_count.x, workgroup_count.y, workgroup_count.z);
+                    }
+                },
+            }
+            if let Some(timestamps) = timestamps.as_ref() {
+                timestamps.record_end(command_buffer);
+            }
             // Barrier to ensure that shader writes are finished before buffer is read back from GPU
             {
-                let buffer_barrier = VkBufferMemoryBarrier::new(
-                    VkAccessFlagBits::VK_ACCESS_SHADER_WRITE_BIT as VkFlags,
-                    VkAccessFlagBits::VK_ACCESS_TRANSFER_READ_BIT as VkFlags |
-                        VkAccessFlagBits::VK_ACCESS_SHADER_READ_BIT as VkFlags,
-                    staging_buffer.device_buffer_memory().buffer(),
-                    0,
-                    VK_WHOLE_SIZE,
-                );
+                let buffer_barriers = staging_buffers.iter()
+                    .map(|v| {
+                        VkBufferMemoryBarrier::new(
+                            VkAccessFlagBits::VK_ACCESS_SHADER_WRITE_BIT as VkFlags,
+                            VkAccessFlagBits::VK_ACCESS_TRANSFER_READ_BIT as VkFlags |
+                                VkAccessFlagBits::VK_ACCESS_SHADER_READ_BIT as VkFlags,
+                            v.device_buffer_memory().buffer(),
+                            0,
+                            VK_WHOLE_SIZE,
+                        )
+                    })
+                    .collect::<Vec<VkBufferMemoryBarrier>>();
                 vkCmdPipelineBarrier(
                     command_buffer,
                     VkPipelineStageFlagBits::VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT as VkFlags,
@@ -108,60 +200,196 @@ impl CommandDispatch {
                         VkPipelineStageFlagBits::VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT as VkFlags,
                     VK_FLAGS_NONE,
                     0, ptr::null(),
-                    1, &buffer_barrier,
+                    buffer_barriers.len() as u32, buffer_barriers.as_ptr(),
                     0, ptr::null(),
                 );
             }
             vkEndCommandBuffer(command_buffer);
             let command_dispatch = CommandDispatch {
                 compute_pipeline: Arc::clone(compute_pipeline),
+                binding_sets,
+                staging_buffers,
                 command_buffer: command_buffer,
                 fence: fence,
+                timestamps,
+                lock: Mutex::new(()),
             };
             Arc::new(command_dispatch)
         }
     }
 
-    pub fn dispatch(&self) {
-        unsafe {
-            let staging_buffer = self.compute_pipeline.staging_buffer();
-            let device = staging_buffer.command_pool().device();
-            let fence = self.fence;
+    // submits the dispatch without waiting for its completion, after the host accesses to the buffer end.
+    // the buffer stays in flight until `wait`, host accesses to it block until then
+    pub fn submit(&self) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let _descriptors = self.binding_sets.iter()
+            .map(|v| v.lock.read().unwrap())
+            .collect::<Vec<_>>();
+        // the previous submission must be complete before the command buffer is submitted again
+        self.fence.wait();
+        // the timestamps are overwritten by the next submission
+        if let Some(timestamps) = self.timestamps.as_ref() {
+            timestamps.resolve();
+        }
+        let device = self.staging_buffers[0].command_pool().device();
+        StagingBuffer::submit_in_flight(&self.staging_buffers, &self.fence, || {
+            self.fence.reset();
             let command_buffer = self.command_buffer;
-            vkResetFences(device.handle(), 1, &fence)
-                .into_result()
-                .unwrap();
             let wait_mask = VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TRANSFER_BIT as VkPipelineStageFlags;
             let submit_info = VkSubmitInfo::with_command_buffer_wait(1, &command_buffer, &wait_mask);
-            vkQueueSubmit(device.queue().handle(), 1, &submit_info, fence);
-            vkWaitForFences(device.handle(), 1, &fence, VK_TRUE, u64::max_value())
-                .into_result()
-                .unwrap();
+            device.queue().submit(&submit_info, self.fence.handle())
+        })?;
+        if let Some(timestamps) = self.timestamps.as_ref() {
+            timestamps.submitted();
+        }
+        Ok(())
+    }
+
+    pub fn wait(&self) {
+        self.fence.wait();
+        for staging_buffer in self.staging_buffers.iter() {
+            staging_buffer.complete_in_flight(&self.fence);
+        }
+        if let Some(timestamps) = self.timestamps.as_ref() {
+            timestamps.resolve();
         }
     }
+
+    // the entry point of the pipeline dispatched
+    #[inline]
+    pub fn entry_point(&self) -> &str {
+        self.compute_pipeline.entry_point()
+    }
+
+    // the GPU time the last dispatch waited for took, between the timestamps written around it.
+    // none unless the context is built with `ContextBuilder::profiling`
+    pub fn gpu_duration(&self) -> Option<Duration> {
+        self.timestamps.as_ref()
+            .and_then(|v| v.last())
+    }
+
+    // blocks while the host is accessing the buffer, see `submit` for the fallible version
+    pub fn dispatch(&self) {
+        self.submit().unwrap();
+        self.wait();
+    }
 }
 
+// the command buffer is only submitted and the fence is only reset under `lock`
+unsafe impl Send for CommandDispatch {}
+unsafe impl Sync for CommandDispatch {}
+
 impl Drop for CommandDispatch {
     fn drop(&mut self) {
         log_debug!("Drop CommandDispatch");
         unsafe {
-            let staging_buffer = self.compute_pipeline.staging_buffer();
-            let command_pool = staging_buffer.command_pool();
+            let command_pool = Arc::clone(self.staging_buffers[0].command_pool());
             let device = command_pool.device();
-            vkDestroyFence(device.handle(), self.fence, ptr::null());
-            self.fence = ptr::null_mut();
+            // the command buffer must not be pending when freed
+            self.wait();
+            let _guard = command_pool.lock();
             vkFreeCommandBuffers(device.handle(), command_pool.handle(), 1, &self.command_buffer);
             self.command_buffer = ptr::null_mut();
         }
     }
 }
 
+#[derive(Copy, Clone)]
 pub struct WorkgroupCount {
     pub x: u32,
     pub y: u32,
     pub z: u32,
 }
 
+impl WorkgroupCount {
+    // fails for counts beyond u32, see `check` for the limit of the device
+    pub(crate) fn new(x: usize, y: usize, z: usize) -> Result<Self> {
+        let mut counts = [0u32; 3];
+        for (axis, &count) in [x, y, z].iter().enumerate() {
+            counts[axis] = u32::try_from(count)
+                .map_err(|_| ErrorCode::WorkgroupCountExceedsLimit { axis, count, limit: u32::MAX })?;
+        }
+        Ok(WorkgroupCount { x: counts[0], y: counts[1], z: counts[2] })
+    }
+
+    // checks the count against maxComputeWorkGroupCount
+    pub(crate) fn check(self, limit: [u32; 3]) -> Result<Self> {
+        let counts = [self.x, self.y, self.z];
+        for (axis, (&count, &limit)) in counts.iter().zip(limit.iter()).enumerate() {
+            if count > limit {
+                return Err(ErrorCode::WorkgroupCountExceedsLimit { axis, count: count as usize, limit }.into())
+            }
+        }
+        Ok(self)
+    }
+
+    // splits the count along x into parts of `limit` at most, each with the workgroup it starts from
+    pub(crate) fn split_x(self, limit: u32) -> Vec<(u32, WorkgroupCount)> {
+        let limit = limit.max(1);
+        (0..self.x).step_by(limit as usize)
+            .map(|base| (base, WorkgroupCount { x: limit.min(self.x - base), ..self }))
+            .collect()
+    }
+
+    // folds a 1D count beyond `limit` into rows of a 2D grid, which runs less than a row more workgroups.
+    // the shader finds its index as `gl_GlobalInvocationID.x + gl_GlobalInvocationID.y * gl_NumWorkGroups.x * gl_WorkGroupSize.x`
+    pub(crate) fn fold_x(self, limit: u32) -> Self {
+        if self.x <= limit || self.y != 1 || self.z != 1 {
+            return self
+        }
+        let rows = self.x.div_ceil(limit.max(1));
+        WorkgroupCount { x: self.x.div_ceil(rows), y: rows, z: 1 }
+    }
+}
+
+// the layout of VkDispatchIndirectCommand, 
+// a binding region holding one lets the GPU decide the workgroup count
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DispatchIndirectCommand {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+// the workgroup count read from the binding region at the offset when the dispatch runs
+pub struct DispatchIndirect {
+    offset: VkDeviceSize,
+}
+
+impl DispatchIndirect {
+    pub(crate) fn new(staging_buffer: &Arc<StagingBuffer>, region_index: usize, offset: usize) -> Result<Self> {
+        let size = mem::size_of::<DispatchIndirectCommand>();
+        let region = staging_buffer.nth_region(region_index)
+            .filter(|v| offset.is_multiple_of(mem::size_of::<u32>()) && offset + size <= v.region_size() as usize)
+            .ok_or(ErrorCode::DispatchIndirectOutOfRange { region: region_index, offset })?;
+        Ok(DispatchIndirect { offset: region.offset() + offset as VkDeviceSize })
+    }
+}
+
+pub enum DispatchCommand {
+    Direct(WorkgroupCount),
+    // gl_WorkGroupID starts from the base, see `PipelineBuilder::dispatch_base`
+    Base(WorkgroupCount, WorkgroupCount),
+    // the offset of VkDispatchIndirectCommand in the device buffer
+    Indirect(VkDeviceSize),
+    // dispatches one after another, each pushing its value as the uint at the offset first
+    Split { parts: Vec<(u32, WorkgroupCount)>, offset: u32 },
+}
+
+impl From<WorkgroupCount> for DispatchCommand {
+    fn from(count: WorkgroupCount) -> Self {
+        DispatchCommand::Direct(count)
+    }
+}
+
+impl From<DispatchIndirect> for DispatchCommand {
+    fn from(indirect: DispatchIndirect) -> Self {
+        DispatchCommand::Indirect(indirect.offset)
+    }
+}
+
+#[derive(Clone)]
 pub struct ConstantEntry {
     size: usize,
     bytes: Vec<u8>,
@@ -171,186 +399,771 @@ impl ConstantEntry {
     pub fn new(bytes: Vec<u8>) -> Self {
         ConstantEntry { size: bytes.len(), bytes }
     }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
 }
 
-pub struct ComputePipeline {
-    handle: VkPipeline,
-    cache: VkPipelineCache,
-    layout: VkPipelineLayout,
+// how a pipeline is created, given by `PipelineBuilder`
+#[derive(Clone, Default)]
+pub struct PipelineOptions {
+    pub(crate) dispatch_base: bool,
+    pub(crate) required_subgroup_size: Option<u32>,
+    pub(crate) full_subgroups: bool,
+    // the specialization constant given the subgroup size
+    pub(crate) subgroup_size_constant: Option<u32>,
+    // the names of the bindings holding descriptor arrays with their max counts
+    pub(crate) descriptor_arrays: Vec<(String, u32)>,
+    // the buffers bound to the sets other than set 0 by set number
+    pub(crate) set_buffers: Vec<(u32, Arc<Buffer>)>,
+    // the names of the bindings taking dynamic offsets with the sizes of their windows
+    pub(crate) dynamic_bindings: Vec<(String, usize)>,
+}
+
+// a binding holding an array of buffer regions, see `PipelineBuilder::descriptor_array`
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DescriptorArray {
+    pub(crate) binding: u32,
+    pub(crate) max_count: u32,
+    // allocated with the max count, only the highest binding of the set can be
+    pub(crate) variable_count: bool,
+}
+
+// a binding seeing a window of its region from the offset each dispatch gives, 
+// see `PipelineBuilder::dynamic_binding`
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DynamicBinding {
+    pub(crate) binding: u32,
+    // the size of the window in bytes
+    pub(crate) range: usize,
+}
+
+// the bindings of a descriptor set
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct DescriptorLayout {
+    // bound to the buffer region of the same index
+    pub(crate) region_bindings: Vec<u32>,
+    pub(crate) arrays: Vec<DescriptorArray>,
+    // in the order of binding numbers, which dynamic offsets are given in
+    pub(crate) dynamic_bindings: Vec<DynamicBinding>,
+}
+
+impl DescriptorLayout {
+    // the storage buffer descriptors a set of the layout takes from its pool
+    pub(crate) fn descriptor_count(&self) -> u32 {
+        self.region_bindings.len() as u32 + self.arrays.iter().map(|v| v.max_count).sum::<u32>()
+    }
+
+    // the dynamic storage buffer descriptors a set of the layout takes from its pool
+    pub(crate) fn dynamic_descriptor_count(&self) -> u32 {
+        self.dynamic_bindings.len() as u32
+    }
+}
+
+pub struct DescriptorSetLayout {
+    handle: VkDescriptorSetLayout,
+    layout: DescriptorLayout,
+    device: Arc<Device>,
+}
+
+impl DescriptorSetLayout {
+    pub fn new(device: &Arc<Device>, layout: DescriptorLayout) -> Arc<Self> {
+        let mut descriptor_set_layout = MaybeUninit::<VkDescriptorSetLayout>::zeroed();
+        unsafe {
+            let mut bindings = layout.region_bindings.iter()
+                .map(|&binding| {
+                    VkDescriptorSetLayoutBinding::new(
+                        VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, 
+                        VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32,
+                        binding,
+                    )
+                })
+                .collect::<Vec<VkDescriptorSetLayoutBinding>>();
+            let mut binding_flags = vec![VK_FLAGS_NONE; bindings.len()];
+            for array in layout.arrays.iter() {
+                let mut binding = VkDescriptorSetLayoutBinding::new(
+                    VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, 
+                    VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32,
+                    array.binding,
+                );
+                binding.descriptorCount = array.max_count;
+                bindings.push(binding);
+                let mut flags = VkDescriptorBindingFlagBits::VK_DESCRIPTOR_BINDING_UPDATE_AFTER_BIND_BIT as VkDescriptorBindingFlags |
+                    VkDescriptorBindingFlagBits::VK_DESCRIPTOR_BINDING_PARTIALLY_BOUND_BIT as VkDescriptorBindingFlags;
+                if array.variable_count {
+                    flags |= VkDescriptorBindingFlagBits::VK_DESCRIPTOR_BINDING_VARIABLE_DESCRIPTOR_COUNT_BIT as VkDescriptorBindingFlags;
+                }
+                binding_flags.push(flags);
+            }
+            for dynamic in layout.dynamic_bindings.iter() {
+                bindings.push(VkDescriptorSetLayoutBinding::new(
+                    VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER_DYNAMIC, 
+                    VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32,
+                    dynamic.binding,
+                ));
+                binding_flags.push(VK_FLAGS_NONE);
+            }
+            let flags_info = VkDescriptorSetLayoutBindingFlagsCreateInfo::new(binding_flags.len() as u32, binding_flags.as_ptr());
+            let mut create_info = VkDescriptorSetLayoutCreateInfo::new(bindings.len() as u32, bindings.as_ptr());
+            if !layout.arrays.is_empty() {
+                create_info.pNext = &flags_info as *const _ as *const c_void;
+                create_info.flags |= VkDescriptorSetLayoutCreateFlagBits::VK_DESCRIPTOR_SET_LAYOUT_CREATE_UPDATE_AFTER_BIND_POOL_BIT as VkDescriptorSetLayoutCreateFlags;
+            }
+            vkCreateDescriptorSetLayout(device.handle(), &create_info, ptr::null(), descriptor_set_layout.as_mut_ptr())
+                .into_result()
+                .unwrap();
+        }
+        let descriptor_set_layout = DescriptorSetLayout {
+            handle: unsafe { descriptor_set_layout.assume_init() },
+            layout,
+            device: Arc::clone(device),
+        };
+        Arc::new(descriptor_set_layout)
+    }
+
+    #[inline]
+    pub fn handle(&self) -> VkDescriptorSetLayout {
+        self.handle
+    }
+
+    #[inline]
+    pub fn layout(&self) -> &DescriptorLayout {
+        &self.layout
+    }
+
+    // descriptor arrays can be bound again after dispatches are recorded
+    #[inline]
+    pub(crate) fn update_after_bind(&self) -> bool {
+        !self.layout.arrays.is_empty()
+    }
+}
+
+// the handle is immutable after creation
+unsafe impl Send for DescriptorSetLayout {}
+unsafe impl Sync for DescriptorSetLayout {}
+
+impl Drop for DescriptorSetLayout {
+    fn drop(&mut self) {
+        log_debug!("Drop DescriptorSetLayout");
+        unsafe {
+            vkDestroyDescriptorSetLayout(self.device.handle(), self.handle, ptr::null());
+            self.handle = ptr::null_mut();
+        }
+    }
+}
+
+// the number of sets of the layout that exhausted the pools a new pool is sized for
+const SETS_PER_POOL: u32 = 16;
+
+// a pool with what is left to allocate from it
+struct DescriptorPool {
+    handle: VkDescriptorPool,
+    update_after_bind: bool,
+    sets: u32,
+    descriptors: u32,
+    dynamic_descriptors: u32,
+}
+
+// allocates descriptor sets from the pools of the context, 
+// adds a pool sized after the layout once the ones so far run out
+pub struct DescriptorAllocator {
+    device: Arc<Device>,
+    pools: Mutex<Vec<DescriptorPool>>,
+}
+
+impl DescriptorAllocator {
+    pub fn new(device: &Arc<Device>) -> Arc<Self> {
+        let allocator = DescriptorAllocator {
+            device: Arc::clone(device),
+            pools: Mutex::new(vec![]),
+        };
+        Arc::new(allocator)
+    }
+
+    // the pool the set is allocated from, which it is freed to by `free`.
+    // fails with VK_ERROR_OUT_OF_POOL_MEMORY when even a new pool cannot hold the set
+    pub fn allocate(&self, layout: &DescriptorSetLayout) -> Result<(VkDescriptorPool, VkDescriptorSet)> {
+        let descriptors = layout.layout().descriptor_count();
+        let dynamic_descriptors = layout.layout().dynamic_descriptor_count();
+        let update_after_bind = layout.update_after_bind();
+        let mut pools = self.pools.lock().unwrap();
+        // pools with enough left can still fail from fragmentation
+        let candidates = pools.iter_mut()
+            .filter(|v| v.update_after_bind == update_after_bind && v.sets > 0)
+            .filter(|v| v.descriptors >= descriptors && v.dynamic_descriptors >= dynamic_descriptors);
+        for pool in candidates {
+            match self.allocate_from(pool.handle, layout) {
+                Ok(descriptor_set) => {
+                    pool.sets -= 1;
+                    pool.descriptors -= descriptors;
+                    pool.dynamic_descriptors -= dynamic_descriptors;
+                    return Ok((pool.handle, descriptor_set))
+                },
+                Err(VkResult::VK_ERROR_OUT_OF_POOL_MEMORY) | Err(VkResult::VK_ERROR_FRAGMENTED_POOL) => (),
+                Err(code) => return Err(code.into()),
+            }
+        }
+        let mut pool = self.create_pool(descriptors.max(1) * SETS_PER_POOL, dynamic_descriptors * SETS_PER_POOL, update_after_bind)?;
+        let descriptor_set = match self.allocate_from(pool.handle, layout) {
+            Ok(descriptor_set) => descriptor_set,
+            Err(code) => {
+                unsafe {
+                    vkDestroyDescriptorPool(self.device.handle(), pool.handle, ptr::null());
+                }
+                return Err(code.into())
+            },
+        };
+        log_debug!("Added descriptor pool for {} descriptors", pool.descriptors);
+        pool.sets -= 1;
+        pool.descriptors -= descriptors;
+        pool.dynamic_descriptors -= dynamic_descriptors;
+        let handle = pool.handle;
+        pools.push(pool);
+        Ok((handle, descriptor_set))
+    }
+
+    fn create_pool(&self, descriptors: u32, dynamic_descriptors: u32, update_after_bind: bool) -> Result<DescriptorPool> {
+        unsafe {
+            let mut handle = MaybeUninit::<VkDescriptorPool>::zeroed();
+            let mut sizes = vec![VkDescriptorPoolSize::new(VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, descriptors)];
+            if dynamic_descriptors > 0 {
+                sizes.push(VkDescriptorPoolSize::new(VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER_DYNAMIC, dynamic_descriptors));
+            }
+            let mut create_info = VkDescriptorPoolCreateInfo::new(SETS_PER_POOL, sizes.len() as u32, sizes.as_ptr());
+            create_info.flags |= VkDescriptorPoolCreateFlagBits::VK_DESCRIPTOR_POOL_CREATE_FREE_DESCRIPTOR_SET_BIT as VkDescriptorPoolCreateFlags;
+            if update_after_bind {
+                create_info.flags |= VkDescriptorPoolCreateFlagBits::VK_DESCRIPTOR_POOL_CREATE_UPDATE_AFTER_BIND_BIT as VkDescriptorPoolCreateFlags;
+            }
+            vkCreateDescriptorPool(self.device.handle(), &create_info, ptr::null(), handle.as_mut_ptr())
+                .into_result()?;
+            let pool = DescriptorPool {
+                handle: handle.assume_init(),
+                update_after_bind,
+                sets: SETS_PER_POOL,
+                descriptors,
+                dynamic_descriptors,
+            };
+            Ok(pool)
+        }
+    }
+
+    fn allocate_from(&self, pool: VkDescriptorPool, layout: &DescriptorSetLayout) -> std::result::Result<VkDescriptorSet, VkResult> {
+        let variable_count = layout.layout().arrays.iter()
+            .find(|v| v.variable_count)
+            .map(|v| v.max_count);
+        let count_info = variable_count.as_ref()
+            .map(|v| VkDescriptorSetVariableDescriptorCountAllocateInfo::new(1, v));
+        let handle = layout.handle();
+        let mut alloc_info = VkDescriptorSetAllocateInfo::new(pool, 1, &handle);
+        if let Some(count_info) = count_info.as_ref() {
+            alloc_info.pNext = count_info as *const _ as *const c_void;
+        }
+        unsafe {
+            let mut descriptor_set = MaybeUninit::<VkDescriptorSet>::zeroed();
+            match vkAllocateDescriptorSets(self.device.handle(), &alloc_info, descriptor_set.as_mut_ptr()) {
+                VkResult::VK_SUCCESS => Ok(descriptor_set.assume_init()),
+                code => Err(code),
+            }
+        }
+    }
+
+    // gives the descriptors of the set back to its pool
+    pub fn free(&self, pool: VkDescriptorPool, descriptor_set: VkDescriptorSet, layout: &DescriptorSetLayout) {
+        let mut pools = self.pools.lock().unwrap();
+        let pool = pools.iter_mut()
+            .find(|v| v.handle == pool)
+            .unwrap();
+        unsafe {
+            vkFreeDescriptorSets(self.device.handle(), pool.handle, 1, &descriptor_set)
+                .into_result()
+                .unwrap();
+        }
+        pool.sets += 1;
+        pool.descriptors += layout.layout().descriptor_count();
+        pool.dynamic_descriptors += layout.layout().dynamic_descriptor_count();
+    }
+}
+
+// the pools are only accessed under `pools`
+unsafe impl Send for DescriptorAllocator {}
+unsafe impl Sync for DescriptorAllocator {}
+
+impl Drop for DescriptorAllocator {
+    fn drop(&mut self) {
+        log_debug!("Drop DescriptorAllocator");
+        let pools = self.pools.get_mut().unwrap();
+        for pool in pools.iter_mut() {
+            unsafe {
+                vkDestroyDescriptorPool(self.device.handle(), pool.handle, ptr::null());
+            }
+            pool.handle = ptr::null_mut();
+        }
+    }
+}
+
+// the regions of a buffer bound to a descriptor set of a pipeline, see `Pipeline::binding_set`
+pub struct BindingSet {
+    set: u32,
     descriptor_pool: VkDescriptorPool,
-    descriptor_set_layout: VkDescriptorSetLayout,
     descriptor_set: VkDescriptorSet,
+    descriptor_allocator: Arc<DescriptorAllocator>,
+    layout: Arc<DescriptorSetLayout>,
     staging_buffer: Arc<StagingBuffer>,
+    // dispatches are not submitted while the descriptor set is updated
+    lock: RwLock<()>,
+}
+
+impl BindingSet {
+    // `arrays` are the regions bound to the descriptor arrays by binding number
+    pub(crate) fn new(set: u32, 
+        layout: &Arc<DescriptorSetLayout>, 
+        descriptor_allocator: &Arc<DescriptorAllocator>,
+        staging_buffer: &Arc<StagingBuffer>, 
+        arrays: &[(u32, Vec<usize>)]) -> Result<Arc<Self>> {
+        let (descriptor_pool, descriptor_set) = descriptor_allocator.allocate(layout)?;
+        for &binding in layout.layout().region_bindings.iter() {
+            Self::write_regions(staging_buffer, descriptor_set, binding, &[binding as usize]);
+        }
+        for (binding, regions) in arrays.iter() {
+            Self::write_regions(staging_buffer, descriptor_set, *binding, regions);
+        }
+        for dynamic in layout.layout().dynamic_bindings.iter() {
+            Self::write_window(staging_buffer, descriptor_set, dynamic);
+        }
+        let binding_set = BindingSet {
+            set,
+            descriptor_pool,
+            descriptor_set,
+            descriptor_allocator: Arc::clone(descriptor_allocator),
+            layout: Arc::clone(layout),
+            staging_buffer: Arc::clone(staging_buffer),
+            lock: RwLock::new(()),
+        };
+        Ok(Arc::new(binding_set))
+    }
+
+    // the regions bound to the consecutive elements of the binding from the first one
+    fn write_regions(staging_buffer: &StagingBuffer, descriptor_set: VkDescriptorSet, binding: u32, regions: &[usize]) {
+        if regions.is_empty() {
+            return
+        }
+        let device = staging_buffer.command_pool().device();
+        let infos = regions.iter()
+            .map(|&index| {
+                let region = staging_buffer.nth_region(index)
+                    .unwrap();
+                VkDescriptorBufferInfo::new(
+                    staging_buffer.device_buffer_memory().buffer(), 
+                    region.offset(), 
+                    region.region_size())
+            })
+            .collect::<Vec<VkDescriptorBufferInfo>>();
+        let mut write_set = VkWriteDescriptorSet::new(
+            descriptor_set, 
+            VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, 
+            binding, 
+            infos.as_ptr());
+        write_set.descriptorCount = infos.len() as u32;
+        unsafe {
+            vkUpdateDescriptorSets(device.handle(), 1, &write_set, 0, ptr::null());
+        }
+    }
+
+    // the window from the start of the region of the same index, moved by the dynamic offset
+    fn write_window(staging_buffer: &StagingBuffer, descriptor_set: VkDescriptorSet, dynamic: &DynamicBinding) {
+        let device = staging_buffer.command_pool().device();
+        let region = staging_buffer.nth_region(dynamic.binding as usize)
+            .unwrap();
+        let info = VkDescriptorBufferInfo::new(
+            staging_buffer.device_buffer_memory().buffer(), 
+            region.offset(), 
+            dynamic.range as VkDeviceSize);
+        let write_set = VkWriteDescriptorSet::new(
+            descriptor_set, 
+            VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER_DYNAMIC, 
+            dynamic.binding, 
+            &info);
+        unsafe {
+            vkUpdateDescriptorSets(device.handle(), 1, &write_set, 0, ptr::null());
+        }
+    }
+
+    // binds the regions to the descriptor array once the dispatches in flight are done.
+    // the dispatches recorded before run with them as the array is updated after bind
+    pub(crate) fn bind_array(&self, binding: u32, regions: &[usize]) {
+        let _guard = self.lock.write().unwrap();
+        self.staging_buffer.wait_in_flight();
+        Self::write_regions(&self.staging_buffer, self.descriptor_set, binding, regions);
+    }
+
+    // the set number the binding set is bound to
+    #[inline]
+    pub fn set(&self) -> u32 {
+        self.set
+    }
+
+    #[inline]
+    pub(crate) fn layout(&self) -> &Arc<DescriptorSetLayout> {
+        &self.layout
+    }
+
+    #[inline]
+    pub(crate) fn staging_buffer(&self) -> &Arc<StagingBuffer> {
+        &self.staging_buffer
+    }
+
+    // the size of the region bound to the binding of the same index
+    pub(crate) fn region_size(&self, binding: u32) -> usize {
+        self.staging_buffer.nth_region(binding as usize)
+            .map(|v| v.region_size() as usize)
+            .unwrap_or(0)
+    }
+}
+
+// the descriptor set is only updated on creation and under `lock`
+unsafe impl Send for BindingSet {}
+unsafe impl Sync for BindingSet {}
+
+impl Drop for BindingSet {
+    fn drop(&mut self) {
+        log_debug!("Drop BindingSet");
+        self.descriptor_allocator.free(self.descriptor_pool, self.descriptor_set, &self.layout);
+        self.descriptor_set = ptr::null_mut();
+    }
+}
+
+// shared among the pipelines built the same, see `PipelineRegistry`
+pub struct ComputePipeline {
+    handle: VkPipeline,
+    layout: VkPipelineLayout,
+    // in the order of set numbers
+    set_layouts: Vec<Arc<DescriptorSetLayout>>,
+    push_constant_size: usize,
     shader_module: Arc<ShaderModule>,
+    entry_point: String,
+    // tells the pipelines apart in the profile
+    id: u64,
+    device: Arc<Device>,
 }
 
+static NEXT_PIPELINE_ID: AtomicU64 = AtomicU64::new(0);
+
 impl ComputePipeline {
-    pub fn new(staging_buffer: &Arc<StagingBuffer>, 
+    // `set_layouts` are in the order of set numbers, starting from set 0
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(device: &Arc<Device>,
+        set_layouts: Vec<Arc<DescriptorSetLayout>>, 
         shader_module: &Arc<ShaderModule>, 
-        spec_constants: Vec<ConstantEntry>) -> Arc<Self> {
-        let command_pool = staging_buffer.command_pool();
-        let device = command_pool.device();
-        let regions = staging_buffer.regions();
+        pipeline_cache: &Arc<PipelineCache>,
+        entry_point: &str,
+        spec_constants: &[(u32, ConstantEntry)],
+        push_constant_size: usize,
+        options: &PipelineOptions) -> Result<Arc<Self>> {
         unsafe {
-            let mut descriptor_pool = MaybeUninit::<VkDescriptorPool>::zeroed();
-            {
-                let size = VkDescriptorPoolSize::new(VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, 1);
-                let create_info = VkDescriptorPoolCreateInfo::new(1, 1, &size);
-                vkCreateDescriptorPool(device.handle(), &create_info, ptr::null(), descriptor_pool.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
-            }
-            let descriptor_pool = descriptor_pool.assume_init();
-            let mut descriptor_set_layout = MaybeUninit::<VkDescriptorSetLayout>::zeroed();
-            {
-                let bindings = regions.iter()
-                    .enumerate()
-                    .map(|(index, _)| {
-                        VkDescriptorSetLayoutBinding::new(
-                            VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, 
-                            VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32,
-                            index as u32,
-                        )
-                    })
-                    .collect::<Vec<VkDescriptorSetLayoutBinding>>();
-                let create_info = VkDescriptorSetLayoutCreateInfo::new(bindings.len() as u32, bindings.as_ptr());
-                vkCreateDescriptorSetLayout(device.handle(), &create_info, ptr::null(), descriptor_set_layout.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
-            }
-            let descriptor_set_layout = descriptor_set_layout.assume_init();
             let mut pipeline_layout = MaybeUninit::<VkPipelineLayout>::zeroed();
             {
-                let create_info = VkPipelineLayoutCreateInfo::new(1, &descriptor_set_layout);
+                let set_layouts = set_layouts.iter()
+                    .map(|v| v.handle())
+                    .collect::<Vec<VkDescriptorSetLayout>>();
+                // a range is declared only when the shader takes push constants
+                let range = VkPushConstantRange::new(
+                    VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32,
+                    0,
+                    push_constant_size as u32);
+                let range_count = if push_constant_size > 0 { 1 } else { 0 };
+                let create_info = VkPipelineLayoutCreateInfo::new(set_layouts.len() as u32, set_layouts.as_ptr(), range_count, &range);
                 vkCreatePipelineLayout(device.handle(), &create_info, ptr::null(), pipeline_layout.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
+                    .into_result()?;
             }
             let pipeline_layout = pipeline_layout.assume_init();
-            let mut descriptor_set = MaybeUninit::<VkDescriptorSet>::zeroed();
-            {
-                let alloc_info = VkDescriptorSetAllocateInfo::new(descriptor_pool, 1, &descriptor_set_layout);
-                vkAllocateDescriptorSets(device.handle(), &alloc_info, descriptor_set.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
-            }
-            let descriptor_set = descriptor_set.assume_init();
-            {
-                let infos = regions.iter()
-                    .map(|region| {
-                        VkDescriptorBufferInfo::new(
-                            staging_buffer.device_buffer_memory().buffer(), 
-                            region.offset(), 
-                            region.region_size())
-                    })
-                    .collect::<Vec<VkDescriptorBufferInfo>>();
-                let write_sets = infos.iter()
-                    .enumerate()
-                    .map(|(index, buffer_info)| {
-                        VkWriteDescriptorSet::new(
-                            descriptor_set, 
-                            VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER, 
-                            index as u32, 
-                            buffer_info)
-                    })
-                    .collect::<Vec<VkWriteDescriptorSet>>();
-                vkUpdateDescriptorSets(device.handle(), write_sets.len() as u32, write_sets.as_ptr(), 0, ptr::null());
-            }
-            // Pipeline Cache
-            let mut pipeline_cache = MaybeUninit::<VkPipelineCache>::zeroed();
-            {
-                let create_info = VkPipelineCacheCreateInfo::new();
-                vkCreatePipelineCache(device.handle(), &create_info, ptr::null(), pipeline_cache.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
-            }
-            let pipeline_cache = pipeline_cache.assume_init();
             let mut compute_pipeline = MaybeUninit::<VkPipeline>::zeroed();
             {
-                let data = spec_constants.iter()
-                    .flat_map(|entry| entry.bytes.iter().cloned())
-                    .collect::<Vec<u8>>();
-                let entries = spec_constants.iter()
-                    .enumerate()
-                    .scan(0usize, |state, (index, entry)| {
-                        let offset = *state;
-                        let entry = VkSpecializationMapEntry::new(
-                            index as u32, 
-                            offset as u32,
-                            entry.size);
-                        *state += entry.size;
-                        Some(entry)
-                    })
-                    .collect::<Vec<VkSpecializationMapEntry>>();
+                // each constant is placed at an offset aligned to its own size
+                let mut data = Vec::<u8>::new();
+                let mut entries = Vec::<VkSpecializationMapEntry>::new();
+                for (id, constant) in spec_constants {
+                    let align = constant.size.max(1);
+                    let offset = data.len().div_ceil(align) * align;
+                    data.resize(offset, 0);
+                    data.extend_from_slice(&constant.bytes);
+                    entries.push(VkSpecializationMapEntry::new(*id, offset as u32, constant.size));
+                }
                 let spec_info = VkSpecializationInfo::new(
                     entries.len() as u32,
                     entries.as_ptr(),
                     data.len(),
                     data.as_ptr() as *const c_void
                 );
-                let name = CString::new("main").unwrap();
-                let stage = VkPipelineShaderStageCreateInfo::new(
+                let name = CString::new(entry_point).unwrap();
+                let mut stage = VkPipelineShaderStageCreateInfo::new(
                     VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT,
                     shader_module.handle(),
                     name.as_ptr(),
                     &spec_info
                 );
-                let create_info = VkComputePipelineCreateInfo::new(stage, pipeline_layout);
-                vkCreateComputePipelines(device.handle(), pipeline_cache, 1, &create_info, ptr::null(), compute_pipeline.as_mut_ptr())
-                    .into_result()
-                    .unwrap();
+                let required_subgroup_size = options.required_subgroup_size
+                    .map(VkPipelineShaderStageRequiredSubgroupSizeCreateInfoEXT::new);
+                if let Some(required_subgroup_size) = required_subgroup_size.as_ref() {
+                    stage.pNext = required_subgroup_size as *const _ as *const c_void;
+                }
+                if options.full_subgroups {
+                    stage.flags |= VkPipelineShaderStageCreateFlagBits::VK_PIPELINE_SHADER_STAGE_CREATE_REQUIRE_FULL_SUBGROUPS_BIT_EXT as VkPipelineShaderStageCreateFlags;
+                }
+                let flags = if options.dispatch_base {
+                    VkPipelineCreateFlagBits::VK_PIPELINE_CREATE_DISPATCH_BASE as VkPipelineCreateFlags
+                } else {
+                    VK_FLAGS_NONE
+                };
+                let create_info = VkComputePipelineCreateInfo::new(flags, stage, pipeline_layout);
+                // the cache is shared among the pipelines of the context
+                let _guard = pipeline_cache.read();
+                let result = vkCreateComputePipelines(device.handle(), pipeline_cache.handle(), 1, &create_info, ptr::null(), compute_pipeline.as_mut_ptr())
+                    .into_result();
+                if let Err(err) = result {
+                    vkDestroyPipelineLayout(device.handle(), pipeline_layout, ptr::null());
+                    return Err(err)
+                }
             }
             let compute_pipeline = compute_pipeline.assume_init();
             let compute_pipeline = ComputePipeline {
                 handle: compute_pipeline,
-                cache: pipeline_cache,
                 layout: pipeline_layout,
-                descriptor_pool: descriptor_pool,
-                descriptor_set_layout: descriptor_set_layout,
-                descriptor_set: descriptor_set,
+                set_layouts,
+                push_constant_size,
                 shader_module: Arc::clone(shader_module),
-                staging_buffer: Arc::clone(staging_buffer),
+                entry_point: entry_point.to_string(),
+                id: NEXT_PIPELINE_ID.fetch_add(1, Ordering::Relaxed),
+                device: Arc::clone(device),
             };
-            Arc::new(compute_pipeline)
+            Ok(Arc::new(compute_pipeline))
         }
     }
 
-    pub fn staging_buffer(&self) -> &Arc<StagingBuffer> {
-        &self.staging_buffer
+    #[inline]
+    pub(crate) fn set_layouts(&self) -> &[Arc<DescriptorSetLayout>] {
+        &self.set_layouts
+    }
+
+    #[inline]
+    pub fn shader_module(&self) -> &Arc<ShaderModule> {
+        &self.shader_module
+    }
+
+    // the size of the push constant range declared in the pipeline layout
+    #[inline]
+    pub fn push_constant_size(&self) -> usize {
+        self.push_constant_size
+    }
+
+    #[inline]
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
     }
 }
 
+// the handles are immutable after creation
+unsafe impl Send for ComputePipeline {}
+unsafe impl Sync for ComputePipeline {}
+
 impl Drop for ComputePipeline {
     fn drop(&mut self) {
         log_debug!("Drop ComputePipeline");
         unsafe {
-            let command_pool = self.staging_buffer.command_pool();
-            let device = command_pool.device();
-            vkDestroyPipelineLayout(device.handle(), self.layout, ptr::null());
+            vkDestroyPipelineLayout(self.device.handle(), self.layout, ptr::null());
             self.layout = ptr::null_mut();
-            vkDestroyDescriptorSetLayout(device.handle(), self.descriptor_set_layout, ptr::null());
-            self.descriptor_set_layout = ptr::null_mut();
-            vkDestroyDescriptorPool(device.handle(), self.descriptor_pool, ptr::null());
-            self.descriptor_pool = ptr::null_mut();
-            vkDestroyPipeline(device.handle(), self.handle, ptr::null());
+            vkDestroyPipeline(self.device.handle(), self.handle, ptr::null());
             self.handle = ptr::null_mut();
-            vkDestroyPipelineCache(device.handle(), self.cache, ptr::null());
-            self.cache = ptr::null_mut();
         }
     }
 }
 
+// what a compiled pipeline depends on, pipelines built with the same share it
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub(crate) code: ShaderCode,
+    pub(crate) entry_point: String,
+    pub(crate) spec_constants: Vec<(u32, Vec<u8>)>,
+    pub(crate) push_constant_size: usize,
+    pub(crate) dispatch_base: bool,
+    pub(crate) required_subgroup_size: Option<u32>,
+    pub(crate) full_subgroups: bool,
+    pub(crate) set_layouts: Vec<DescriptorLayout>,
+}
+
+// the number of pipelines no one uses kept for the next ones built the same
+const UNUSED_PIPELINE_CAPACITY: usize = 64;
+
+struct RegistryEntry {
+    // empty until the first build compiles the pipeline, the others building it meanwhile wait on the lock
+    slot: Arc<Mutex<Option<Arc<ComputePipeline>>>>,
+    last_used: u64,
+}
+
+impl RegistryEntry {
+    // the slot is only locked by someone else while it is held outside the registry
+    fn is_unused(&self) -> bool {
+        if Arc::strong_count(&self.slot) > 1 {
+            return false
+        }
+        match self.slot.try_lock() {
+            Ok(slot) => slot.as_ref().map(|v| Arc::strong_count(v) == 1).unwrap_or(true),
+            Err(_) => false,
+        }
+    }
+
+    fn is_compiled(&self) -> bool {
+        self.slot.try_lock()
+            .map(|v| v.is_some())
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Default)]
+struct RegistryState {
+    entries: HashMap<PipelineKey, RegistryEntry>,
+    // advanced on each lookup to order the entries by their last use
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+// the compiled pipelines of a context by what they are built with
+pub struct PipelineRegistry {
+    state: Mutex<RegistryState>,
+}
+
+impl PipelineRegistry {
+    pub fn new() -> Arc<Self> {
+        let registry = PipelineRegistry {
+            state: Mutex::new(RegistryState::default()),
+        };
+        Arc::new(registry)
+    }
+
+    // the pipeline built the same before if any, otherwise the one `create` builds.
+    // the registry is not locked while creating, only the builds of the same pipeline wait for it
+    pub(crate) fn get_or_create(&self, key: PipelineKey, create: impl FnOnce() -> Result<Arc<ComputePipeline>>) -> Result<Arc<ComputePipeline>> {
+        let slot = {
+            let mut guard = self.state.lock().unwrap();
+            let state = &mut *guard;
+            state.clock += 1;
+            let entry = state.entries.entry(key)
+                .or_insert_with(|| RegistryEntry { slot: Arc::new(Mutex::new(None)), last_used: 0 });
+            entry.last_used = state.clock;
+            Arc::clone(&entry.slot)
+        };
+        // a build that panicked left the slot empty for the next one to compile
+        let mut compiled = slot.lock().unwrap_or_else(|v| v.into_inner());
+        if let Some(compute_pipeline) = compiled.as_ref() {
+            self.state.lock().unwrap().hits += 1;
+            return Ok(Arc::clone(compute_pipeline))
+        }
+        let compute_pipeline = create()?;
+        *compiled = Some(Arc::clone(&compute_pipeline));
+        drop(compiled);
+        drop(slot);
+        let mut state = self.state.lock().unwrap();
+        state.misses += 1;
+        Self::evict(&mut state, UNUSED_PIPELINE_CAPACITY);
+        Ok(compute_pipeline)
+    }
+
+    // drops the least recently used pipelines no one else holds beyond the capacity,
+    // along with the slots a failed build left empty
+    fn evict(state: &mut RegistryState, capacity: usize) -> usize {
+        state.entries.retain(|_, v| v.is_compiled() || !v.is_unused());
+        let mut unused: Vec<(u64, PipelineKey)> = state.entries.iter()
+            .filter(|(_, v)| v.is_unused())
+            .map(|(key, v)| (v.last_used, key.clone()))
+            .collect();
+        if unused.len() <= capacity {
+            return 0
+        }
+        unused.sort_by_key(|(last_used, _)| *last_used);
+        let count = unused.len() - capacity;
+        for (_, key) in unused.into_iter().take(count) {
+            state.entries.remove(&key);
+        }
+        state.evictions += count as u64;
+        count
+    }
+
+    // drops all the pipelines no one else holds, returns how many
+    pub fn evict_unused(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        Self::evict(&mut state, 0)
+    }
+
+    pub fn stats(&self) -> PipelineRegistryStats {
+        let state = self.state.lock().unwrap();
+        PipelineRegistryStats {
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            entries: state.entries.values().filter(|v| v.is_compiled()).count(),
+        }
+    }
+}
+
+// how often pipelines have been shared, see `Context::pipeline_stats`
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PipelineRegistryStats {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    entries: usize,
+}
+
+impl PipelineRegistryStats {
+    // builds that took a pipeline compiled before
+    #[inline]
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    // builds that compiled a pipeline
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    // pipelines dropped while no one used them
+    #[inline]
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    // pipelines kept, including the ones no one uses
+    #[inline]
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+}
+
 pub struct StagingBuffer {
-    buffer_size: VkDeviceSize,
     regions: Vec<StagingBufferRegion>,
     host_buffer_memory: Arc<BufferMemory>,
     device_buffer_memory: Arc<BufferMemory>,
     command_pool: Arc<CommandPool>,
-    mapped: *mut c_void,
+    profiler: Option<Arc<Profiler>>,
 }
 
 impl StagingBuffer {
-    pub fn new(command_pool: &Arc<CommandPool>, region_sizes: &[usize]) -> Arc<Self> {
+    // the transfers and dispatches of the buffer are measured when `profiler` is given
+    pub fn new(command_pool: &Arc<CommandPool>, region_sizes: &[usize], profiler: Option<&Arc<Profiler>>) -> Arc<Self> {
         let device = command_pool.device();
         // adjusts each region sizes considering allocation granularity size
         let device_properties = device.physical_device().properties();
@@ -377,12 +1190,19 @@ impl StagingBuffer {
             VkMemoryPropertyFlagBits::VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT as u32 |
                 VkMemoryPropertyFlagBits::VK_MEMORY_PROPERTY_HOST_CACHED_BIT as u32,
             buffer_size).unwrap();
-        // device buffer
+        // device buffer, whose address shaders can take once the device enables it
+        let device_address_usage = if device.is_enabled(Feature::BufferDeviceAddress) {
+            VkBufferUsageFlagBits::VK_BUFFER_USAGE_SHADER_DEVICE_ADDRESS_BIT as u32
+        } else {
+            VK_FLAGS_NONE
+        };
         let device_buffer_memory = BufferMemory::new(
             device,
             VkBufferUsageFlagBits::VK_BUFFER_USAGE_TRANSFER_SRC_BIT as u32 | 
                 VkBufferUsageFlagBits::VK_BUFFER_USAGE_TRANSFER_DST_BIT as u32 |
-                VkBufferUsageFlagBits::VK_BUFFER_USAGE_STORAGE_BUFFER_BIT as u32,
+                VkBufferUsageFlagBits::VK_BUFFER_USAGE_STORAGE_BUFFER_BIT as u32 |
+                VkBufferUsageFlagBits::VK_BUFFER_USAGE_INDIRECT_BUFFER_BIT as u32 |
+                device_address_usage,
             VkMemoryPropertyFlagBits::VK_MEMORY_PROPERTY_DEVICE_LOCAL_BIT as u32,
             buffer_size).unwrap();
         // mapping
@@ -402,25 +1222,26 @@ impl StagingBuffer {
                     command_pool, 
                     &host_buffer_memory, 
                     &device_buffer_memory,
+                    profiler,
                     mapped);
                 *state += size.allocation_size;
                 Some(region)
             })
             .collect::<Vec<_>>();
         let staging_buffer = StagingBuffer {
-            buffer_size: buffer_size,
             regions: regions,
             host_buffer_memory: host_buffer_memory,
             device_buffer_memory: device_buffer_memory,
             command_pool: Arc::clone(command_pool),
-            mapped: mapped,
+            profiler: profiler.cloned(),
         };
         Arc::new(staging_buffer)
     }
 
     pub fn write_region_with_slice<ItemType>(&self, region_index: usize, access: impl FnOnce(&mut [ItemType])) {
         let region = self.nth_region(region_index)
-        .unwrap();
+            .unwrap();
+        let _access = region.write();
         unsafe {
             access(region.as_mut_slice::<ItemType>());
         }
@@ -431,6 +1252,7 @@ impl StagingBuffer {
     pub fn write_region<DataType>(&self, region_index: usize, access: impl FnOnce(&mut DataType)) {
         let region = self.nth_region(region_index)
             .unwrap();
+        let _access = region.write();
         unsafe {
             access(region.as_mut());
         }
@@ -441,8 +1263,7 @@ impl StagingBuffer {
     pub fn read_region_with_slice<ItemType>(&self, region_index: usize, access: impl FnOnce(&[ItemType])) {
         let region = self.nth_region(region_index)
             .unwrap();
-        region.transfer_device_to_host();
-        region.invalidate_mapped_memory_range();
+        let _access = region.read();
         unsafe {
             access(region.as_slice::<ItemType>());
         }
@@ -451,18 +1272,68 @@ impl StagingBuffer {
     pub fn read_region<DataType>(&self, region_index: usize, access: impl FnOnce(&DataType)) {
         let region = self.nth_region(region_index)
             .unwrap();
-        region.transfer_device_to_host();
-        region.invalidate_mapped_memory_range();
+        let _access = region.read();
         unsafe {
             access(region.as_ref::<DataType>());
         }
     }
 
+    // submits GPU work using the whole buffers once the host is done accessing their regions.
+    // the regions stay locked during the submission so that nobody waits for the fence before it is submitted
+    pub(crate) fn submit_in_flight(staging_buffers: &[Arc<StagingBuffer>], fence: &Arc<Fence>, submit: impl FnOnce() -> Result<()>) -> Result<()> {
+        let mut accesses: Vec<MutexGuard<'_, RegionAccess>> = loop {
+            let accesses: Vec<MutexGuard<'_, RegionAccess>> = staging_buffers.iter()
+                .flat_map(|v| v.regions.iter())
+                .map(|v| v.access.lock().unwrap())
+                .collect();
+            let borrowed = accesses.iter()
+                .position(|v| v.writer || v.readers > 0);
+            let borrowed = match borrowed {
+                Some(borrowed) => borrowed,
+                None => break accesses,
+            };
+            // waits for the region without holding the others, then locks them all again
+            drop(accesses);
+            let region = staging_buffers.iter()
+                .flat_map(|v| v.regions.iter())
+                .nth(borrowed)
+                .unwrap();
+            drop(region.wait_released(|v| v.writer || v.readers > 0));
+        };
+        submit()?;
+        for access in accesses.iter_mut() {
+            access.in_flight.retain(|v| !Arc::ptr_eq(v, fence));
+            access.in_flight.push(Arc::clone(fence));
+        }
+        Ok(())
+    }
+
+    // waits for the dispatches submitted so far
+    pub(crate) fn wait_in_flight(&self) {
+        for region in self.regions.iter() {
+            let in_flight = region.access.lock().unwrap().in_flight.clone();
+            region.wait_in_flight(in_flight);
+        }
+    }
+
+    // the fence has been signaled
+    pub(crate) fn complete_in_flight(&self, fence: &Arc<Fence>) {
+        for region in self.regions.iter() {
+            let mut access = region.access.lock().unwrap();
+            access.in_flight.retain(|v| !Arc::ptr_eq(v, fence));
+        }
+    }
+
     #[inline]
     pub fn command_pool(&self) -> &Arc<CommandPool> {
         &self.command_pool
     }
 
+    #[inline]
+    pub(crate) fn profiler(&self) -> Option<&Arc<Profiler>> {
+        self.profiler.as_ref()
+    }
+
     #[inline]
     pub fn host_buffer_memory(&self) -> &Arc<BufferMemory> {
         &self.host_buffer_memory
@@ -474,16 +1345,23 @@ impl StagingBuffer {
     }
 
     #[inline]
-    fn nth_region(&self, index: usize) -> Option<&StagingBufferRegion> {
-        self.regions.get(index)
+    // the device address of the region, see `BufferMemory::device_address`
+    pub fn region_device_address(&self, region_index: usize) -> Result<VkDeviceAddress> {
+        let region = self.nth_region(region_index)
+            .unwrap();
+        let address = self.device_buffer_memory.device_address()?;
+        Ok(address + region.offset())
     }
 
-    #[inline]
-    fn regions(&self) -> &Vec<StagingBufferRegion> {
-        &self.regions
+    fn nth_region(&self, index: usize) -> Option<&StagingBufferRegion> {
+        self.regions.get(index)
     }
 }
 
+// the mapped memory is only accessed through the regions tracking their accesses
+unsafe impl Send for StagingBuffer {}
+unsafe impl Sync for StagingBuffer {}
+
 impl Drop for StagingBuffer {
     fn drop(&mut self) {
         log_debug!("Drop StagingBuffer");
@@ -506,12 +1384,51 @@ pub struct StagingBufferRegion {
     size: StagingBufferRegionSize,
     command_pool: Arc<CommandPool>,
     host_buffer_memory: Arc<BufferMemory>,
-    device_buffer_memory: Arc<BufferMemory>,
     host_to_device_command: VkCommandBuffer,
     device_to_host_command: VkCommandBuffer,
     host_to_device_fence: VkFence,
     device_to_host_fence: VkFence,
+    // written around the copies when the context is profiling
+    host_to_device_timestamps: Option<TimestampQuery>,
+    device_to_host_timestamps: Option<TimestampQuery>,
     region_ptr: *mut u8,
+    access: Mutex<RegionAccess>,
+    // notified when the first reader finishes transferring the region back to the host
+    readable: Condvar,
+    // notified when a host access to the region ends
+    released: Condvar,
+}
+
+// accesses to a region by the host and by the GPU
+#[derive(Default)]
+struct RegionAccess {
+    readers: usize,
+    writer: bool,
+    // whether the readers can read the mapped memory
+    readable: bool,
+    // fences of the submitted dispatches using the region
+    in_flight: Vec<Arc<Fence>>,
+}
+
+// releases the host access to a region when dropped
+struct RegionAccessGuard<'a> {
+    region: &'a StagingBufferRegion,
+    write: bool,
+}
+
+impl<'a> Drop for RegionAccessGuard<'a> {
+    fn drop(&mut self) {
+        let mut access = self.region.access.lock().unwrap();
+        if self.write {
+            access.writer = false;
+        } else {
+            access.readers -= 1;
+            if access.readers == 0 {
+                access.readable = false;
+            }
+        }
+        self.region.released.notify_all();
+    }
 }
 
 impl StagingBufferRegion {
@@ -523,9 +1440,16 @@ impl StagingBufferRegion {
         command_pool: &Arc<CommandPool>,
         host_buffer_memory: &Arc<BufferMemory>,
         device_buffer_memory: &Arc<BufferMemory>,
+        profiler: Option<&Arc<Profiler>>,
         mapped: *mut c_void) -> StagingBufferRegion {
         let copy_region = VkBufferCopy::new(offset, size.allocation_size);
+        let copy_size = size.allocation_size as usize;
+        let host_to_device_timestamps = profiler
+            .map(|v| TimestampQuery::new(v, ProfiledOperation::HostToDevice { size: copy_size }));
+        let device_to_host_timestamps = profiler
+            .map(|v| TimestampQuery::new(v, ProfiledOperation::DeviceToHost { size: copy_size }));
         let device = command_pool.device();
+        let _guard = command_pool.lock();
         unsafe {
             let mut host_to_device_command = MaybeUninit::<VkCommandBuffer>::zeroed();
             let mut device_to_host_command = MaybeUninit::<VkCommandBuffer>::zeroed();
@@ -557,6 +1481,9 @@ impl StagingBufferRegion {
                 vkBeginCommandBuffer(host_to_device_command, &begin_info)
                     .into_result()
                     .unwrap();
+                if let Some(timestamps) = host_to_device_timestamps.as_ref() {
+                    timestamps.record_begin(host_to_device_command);
+                }
                 // copy to staging buffer
                 vkCmdCopyBuffer(
                     host_to_device_command,
@@ -565,6 +1492,9 @@ impl StagingBufferRegion {
                     1,
                     &copy_region
                 );
+                if let Some(timestamps) = host_to_device_timestamps.as_ref() {
+                    timestamps.record_end(host_to_device_command);
+                }
                 vkEndCommandBuffer(host_to_device_command);
             }
             let device_to_host_command = device_to_host_command.assume_init();
@@ -576,6 +1506,9 @@ impl StagingBufferRegion {
                 // Submission guarantees the host write being complete, as per
                 // https://www.khronos.org/registry/vulkan/specs/1.0/html/vkspec.html#synchronization-submission-host-writes
                 // So no need for a barrier before the transfer for that purpose.
+                if let Some(timestamps) = device_to_host_timestamps.as_ref() {
+                    timestamps.record_begin(device_to_host_command);
+                }
                 // Read back to host visible buffer
                 vkCmdCopyBuffer(
                     device_to_host_command, 
@@ -583,6 +1516,9 @@ impl StagingBufferRegion {
                     host_buffer_memory.buffer(),
                     1,
                     &copy_region);
+                if let Some(timestamps) = device_to_host_timestamps.as_ref() {
+                    timestamps.record_end(device_to_host_command);
+                }
                 // Barrier to ensure that buffer copy is finished before host reading from it
                 {
                     let buffer_barrier = VkBufferMemoryBarrier::new(
@@ -609,18 +1545,78 @@ impl StagingBufferRegion {
                 size: size,
                 command_pool: Arc::clone(command_pool),
                 host_buffer_memory: Arc::clone(host_buffer_memory),
-                device_buffer_memory: Arc::clone(device_buffer_memory),
                 host_to_device_command,
                 device_to_host_command,
                 host_to_device_fence: host_to_device_fence.assume_init(),
                 device_to_host_fence: device_to_host_fence.assume_init(),
+                host_to_device_timestamps,
+                device_to_host_timestamps,
                 region_ptr: (mapped as *mut u8).offset(offset as isize),
+                access: Mutex::new(RegionAccess::default()),
+                readable: Condvar::new(),
+                released: Condvar::new(),
             };
             region
         }
     }
 
-    pub fn transfer_host_to_device(&self) {
+    // borrows the mapped memory exclusively once the other host accesses end and the GPU is done with the region.
+    // blocks as `Mutex` does, so a thread borrowing the region again while borrowing it never returns
+    fn write(&self) -> RegionAccessGuard<'_> {
+        let in_flight = {
+            let mut access = self.wait_released(|v| v.writer || v.readers > 0);
+            access.writer = true;
+            access.in_flight.clone()
+        };
+        let guard = RegionAccessGuard { region: self, write: true };
+        self.wait_in_flight(in_flight);
+        guard
+    }
+
+    // borrows the mapped memory shared with the other readers once the GPU is done with the region,
+    // the first reader transfers the region back to the host
+    fn read(&self) -> RegionAccessGuard<'_> {
+        let (is_first, in_flight) = {
+            let mut access = self.wait_released(|v| v.writer);
+            access.readers += 1;
+            (access.readers == 1, access.in_flight.clone())
+        };
+        let guard = RegionAccessGuard { region: self, write: false };
+        if is_first {
+            self.wait_in_flight(in_flight);
+            self.transfer_device_to_host();
+            self.invalidate_mapped_memory_range();
+            let mut access = self.access.lock().unwrap();
+            access.readable = true;
+            self.readable.notify_all();
+        } else {
+            let mut access = self.access.lock().unwrap();
+            while !access.readable {
+                access = self.readable.wait(access).unwrap();
+            }
+        }
+        guard
+    }
+
+    // locks the accesses once the host accesses blocking the caller end
+    fn wait_released(&self, is_blocked: impl Fn(&RegionAccess) -> bool) -> MutexGuard<'_, RegionAccess> {
+        let mut access = self.access.lock().unwrap();
+        while is_blocked(&access) {
+            access = self.released.wait(access).unwrap();
+        }
+        access
+    }
+
+    // no dispatch can be submitted meanwhile as the host is accessing the region
+    fn wait_in_flight(&self, in_flight: Vec<Arc<Fence>>) {
+        for fence in in_flight.iter() {
+            fence.wait();
+        }
+        let mut access = self.access.lock().unwrap();
+        access.in_flight.retain(|v| !in_flight.iter().any(|fence| Arc::ptr_eq(v, fence)));
+    }
+
+    fn transfer_host_to_device(&self) {
         let device = self.command_pool.device();
         let command_buffer = self.host_to_device_command;
         let fence = self.host_to_device_fence;
@@ -630,14 +1626,19 @@ impl StagingBufferRegion {
                 .unwrap();
             let wait_mask = VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TRANSFER_BIT as VkPipelineStageFlags;
             let submit_info = VkSubmitInfo::with_command_buffer_wait(1, &command_buffer, &wait_mask);
-            vkQueueSubmit(device.queue().handle(), 1, &submit_info, fence);
+            device.queue().submit(&submit_info, fence)
+                .unwrap();
             vkWaitForFences(device.handle(), 1, &fence, VK_TRUE, u64::max_value())
                 .into_result()
                 .unwrap();
         }
+        if let Some(timestamps) = self.host_to_device_timestamps.as_ref() {
+            timestamps.submitted();
+            timestamps.resolve();
+        }
     }
 
-    pub fn transfer_device_to_host(&self) {
+    fn transfer_device_to_host(&self) {
         let device = self.command_pool.device();
         let command_buffer = self.device_to_host_command;
         let fence = self.device_to_host_fence;
@@ -647,11 +1648,16 @@ impl StagingBufferRegion {
                 .unwrap();
             let wait_mask = VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TRANSFER_BIT as VkPipelineStageFlags;
             let submit_info = VkSubmitInfo::with_command_buffer_wait(1, &command_buffer, &wait_mask);
-            vkQueueSubmit(device.queue().handle(), 1, &submit_info, fence);
+            device.queue().submit(&submit_info, fence)
+                .unwrap();
             vkWaitForFences(device.handle(), 1, &fence, VK_TRUE, u64::max_value())
                 .into_result()
                 .unwrap();
         }
+        if let Some(timestamps) = self.device_to_host_timestamps.as_ref() {
+            timestamps.submitted();
+            timestamps.resolve();
+        }
     }
 
     fn invalidate_mapped_memory_range(&self) {
@@ -715,12 +1721,16 @@ impl StagingBufferRegion {
     }
 }
 
+unsafe impl Send for StagingBufferRegion {}
+unsafe impl Sync for StagingBufferRegion {}
+
 impl Drop for StagingBufferRegion {
     fn drop(&mut self) {
         log_debug!("Drop StagingBufferRegion");
         unsafe {
             let command_pool = &self.command_pool;
             let device = command_pool.device();
+            let _guard = command_pool.lock();
             vkDestroyFence(device.handle(), self.host_to_device_fence, ptr::null());
             self.host_to_device_fence = ptr::null_mut();
             vkDestroyFence(device.handle(), self.device_to_host_fence, ptr::null());
@@ -731,4 +1741,61 @@ impl Drop for StagingBufferRegion {
             self.device_to_host_command = ptr::null_mut();
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(count: WorkgroupCount) -> [u32; 3] {
+        [count.x, count.y, count.z]
+    }
+
+    #[test]
+    fn workgroup_count_new() {
+        assert_eq!(counts(WorkgroupCount::new(1, 2, 3).unwrap()), [1, 2, 3]);
+        if let Some(count) = (u32::MAX as usize).checked_add(1) {
+            let error = WorkgroupCount::new(1, count, 1).err().unwrap();
+            assert!(matches!(error.code(), ErrorCode::WorkgroupCountExceedsLimit { axis: 1, limit: u32::MAX, .. }));
+        }
+    }
+
+    #[test]
+    fn workgroup_count_check() {
+        let limit = [65535, 65535, 64];
+        assert!(WorkgroupCount::new(65535, 1, 64).unwrap().check(limit).is_ok());
+        let error = WorkgroupCount::new(65536, 1, 1).unwrap().check(limit).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::WorkgroupCountExceedsLimit { axis: 0, count: 65536, limit: 65535 }));
+        let error = WorkgroupCount::new(1, 1, 65).unwrap().check(limit).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::WorkgroupCountExceedsLimit { axis: 2, count: 65, limit: 64 }));
+    }
+
+    #[test]
+    fn workgroup_count_split_x() {
+        let parts: Vec<_> = WorkgroupCount::new(65537, 2, 3).unwrap()
+            .split_x(65535)
+            .into_iter()
+            .map(|(base, count)| (base, counts(count)))
+            .collect();
+        assert_eq!(parts, [(0, [65535, 2, 3]), (65535, [2, 2, 3])]);
+        let parts: Vec<_> = WorkgroupCount::new(8, 1, 1).unwrap()
+            .split_x(4)
+            .into_iter()
+            .map(|(base, count)| (base, count.x))
+            .collect();
+        assert_eq!(parts, [(0, 4), (4, 4)]);
+        assert_eq!(WorkgroupCount::new(3, 1, 1).unwrap().split_x(4).len(), 1);
+        assert!(WorkgroupCount::new(0, 1, 1).unwrap().split_x(4).is_empty());
+    }
+
+    #[test]
+    fn workgroup_count_fold_x() {
+        assert_eq!(counts(WorkgroupCount::new(100_000, 1, 1).unwrap().fold_x(65535)), [50_000, 2, 1]);
+        // the last row runs less than a row more workgroups
+        let count = WorkgroupCount::new(196_609, 1, 1).unwrap().fold_x(65535);
+        assert_eq!(counts(count), [49_153, 4, 1]);
+        assert!(count.x * count.y - 196_609 < count.y);
+        // counts within the limit and 2D counts are left as they are
+        assert_eq!(counts(WorkgroupCount::new(65535, 1, 1).unwrap().fold_x(65535)), [65535, 1, 1]);
+        assert_eq!(counts(WorkgroupCount::new(70_000, 2, 1).unwrap().fold_x(65535)), [70_000, 2, 1]);
+    }
+}
@@ -1,6 +1,9 @@
 
 use crate::vk::VkResult;
+use crate::features::Feature;
+use crate::reflection::{DescriptorType, ScalarType, capability_name};
 
+use std::fmt;
 use std::result;
 
 pub type Result<T> = result::Result<T, Error>;
@@ -10,9 +13,229 @@ pub enum ErrorCode {
     VkResult(VkResult),
     FFI(std::ffi::NulError),
     SuitablePhysicalDeviceNotFound,
+    FeatureUnsupported(Feature),
+    FeatureNotEnabled(Feature),
+    TimestampUnsupported,
+    SubgroupSizeUnsupported { size: u32, min: u32, max: u32 },
+    WorkgroupSizeExceedsSubgroupLimit { invocations: u32, limit: u32 },
+    LocalSizeNotMultipleOfSubgroupSize { local_size_x: u32, subgroup_size: u32 },
     SuitableBufferMemoryTypeNotFound,
     ShaderLoadIO(std::io::Error),
     ShaderLoadUnaligned,
+    ShaderMalformed,
+    ShaderTruncated,
+    ShaderMagicNumberMismatch(u32),
+    ShaderEndiannessMismatch,
+    ShaderIdOutOfBound { id: u32, bound: u32 },
+    ShaderComputeEntryPointNotFound,
+    ShaderVersionUnsupported { version: (u32, u32), supported: (u32, u32) },
+    ShaderCapabilityUnsupported(u32),
+    ShaderEntryPointNotFound(String),
+    BindingSetUnsupported { set: u32, binding: u32 },
+    BindingNotFound { set: u32, binding: u32 },
+    BindingTypeMismatch { set: u32, binding: u32, descriptor_type: DescriptorType },
+    BindingSizeMismatch { set: u32, binding: u32, expected: usize, stride: Option<usize>, actual: usize },
+    BindingNameNotFound(String),
+    BindingArrayLengthMissing { set: u32, binding: u32 },
+    DescriptorArrayNotDeclared(String),
+    DescriptorArrayBindingUndeclared { set: u32, binding: u32 },
+    DescriptorArrayBindingSingle { set: u32, binding: u32 },
+    DescriptorArrayLengthExceedsCount { set: u32, binding: u32, length: u32, max_count: u32 },
+    DescriptorArrayCountExceeded { name: String, count: usize, max_count: u32 },
+    DescriptorArrayRegionsInGivenSet { name: String, set: u32 },
+    DescriptorSetCountExceedsLimit { count: u32, limit: u32 },
+    DescriptorSetZeroGiven,
+    BindingSetLayoutMismatch { set: u32 },
+    DynamicBindingNotDeclared(String),
+    DynamicBindingUnsupported { set: u32, binding: u32 },
+    DynamicBindingCountExceedsLimit { count: u32, limit: u32 },
+    DynamicOffsetMisaligned { offset: usize, alignment: usize },
+    DynamicOffsetOutOfRange { set: u32, binding: u32, offset: usize, range: usize, size: usize },
+    SpecializationConstantNotFound(u32),
+    SpecializationConstantNameNotFound(String),
+    SpecializationConstantTypeMismatch { id: u32, expected: ScalarType, actual: ScalarType },
+    SpecializationConstantSizeMismatch { id: u32, expected: usize, actual: usize },
+    PushConstantNameNotFound(String),
+    PushConstantMemberSizeMismatch { name: String, expected: usize, actual: usize },
+    PushConstantTypeMismatch { name: String, expected: ScalarType, actual: ScalarType },
+    PushConstantOverlap { name: String },
+    PushConstantSizeMismatch { expected: usize, actual: usize },
+    PushConstantMisaligned { offset: usize, size: usize },
+    PushConstantSizeExceedsLimit { size: usize, limit: usize },
+    WorkgroupCountExceedsLimit { axis: usize, count: usize, limit: u32 },
+    InvocationCountExceedsLimit { axis: usize, count: usize },
+    DispatchIndirectOutOfRange { region: usize, offset: usize },
+    DispatchBaseNotEnabled,
+    BindingBufferMismatch,
+    PipelineCacheIO(std::io::Error),
+    ShaderCompile(Vec<ShaderDiagnostic>),
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorCode::VkResult(code) => write!(f, "Vulkan call failed with {:?}", code),
+            ErrorCode::FFI(error) => write!(f, "{}", error),
+            ErrorCode::SuitablePhysicalDeviceNotFound => write!(f, "no suitable physical device found"),
+            ErrorCode::FeatureUnsupported(feature) => write!(f, "no physical device supports the feature {:?}", feature),
+            ErrorCode::FeatureNotEnabled(feature) => write!(f, "the feature {:?} is not enabled on the device", feature),
+            ErrorCode::TimestampUnsupported => write!(f, "the compute queue of the device writes no timestamps"),
+            ErrorCode::SubgroupSizeUnsupported { size, min, max } => 
+                write!(f, "subgroup size {} is not a power of two between {} and {} that compute shaders can require", size, min, max),
+            ErrorCode::WorkgroupSizeExceedsSubgroupLimit { invocations, limit } => 
+                write!(f, "workgroup of {} invocations exceeds {} for the required subgroup size", invocations, limit),
+            ErrorCode::LocalSizeNotMultipleOfSubgroupSize { local_size_x, subgroup_size } => 
+                write!(f, "local_size_x {} is not a multiple of the subgroup size {} for full subgroups", local_size_x, subgroup_size),
+            ErrorCode::SuitableBufferMemoryTypeNotFound => write!(f, "no suitable buffer memory type found"),
+            ErrorCode::ShaderLoadIO(error) => write!(f, "failed to load shader: {}", error),
+            ErrorCode::ShaderLoadUnaligned => write!(f, "shader code size is not a non-zero multiple of 4"),
+            ErrorCode::ShaderMalformed => write!(f, "shader code is not a well-formed SPIR-V module"),
+            ErrorCode::ShaderTruncated => write!(f, "shader code ends unexpectedly, it may be truncated"),
+            ErrorCode::ShaderMagicNumberMismatch(magic) => 
+                write!(f, "shader code starts with {:#010x}, not the SPIR-V magic number", magic),
+            ErrorCode::ShaderEndiannessMismatch => write!(f, "shader code is SPIR-V of the other endianness"),
+            ErrorCode::ShaderIdOutOfBound { id, bound } => 
+                write!(f, "shader id {} is not less than the id bound {}", id, bound),
+            ErrorCode::ShaderComputeEntryPointNotFound => write!(f, "shader has no compute entry point"),
+            ErrorCode::ShaderVersionUnsupported { version, supported } => 
+                write!(f, "shader is SPIR-V {}.{}, the device supports up to SPIR-V {}.{}", 
+                    version.0, version.1, supported.0, supported.1),
+            ErrorCode::ShaderCapabilityUnsupported(capability) => match capability_name(*capability) {
+                Some(name) => write!(f, "shader requires capability {} which the device has not enabled", name),
+                None => write!(f, "shader requires capability {} which the device has not enabled", capability),
+            },
+            ErrorCode::ShaderEntryPointNotFound(name) => 
+                write!(f, "shader has no compute entry point named {:?}", name),
+            ErrorCode::BindingSetUnsupported { set, binding } => 
+                write!(f, "shader binding (set = {}, binding = {}) uses a descriptor set without a buffer", set, binding),
+            ErrorCode::BindingNotFound { set, binding } => 
+                write!(f, "shader binding (set = {}, binding = {}) has no corresponding buffer region", set, binding),
+            ErrorCode::BindingTypeMismatch { set, binding, descriptor_type } => 
+                write!(f, "shader binding (set = {}, binding = {}) is a {:?}, not a storage buffer", set, binding, descriptor_type),
+            ErrorCode::BindingSizeMismatch { set, binding, expected, stride: Some(stride), actual } => 
+                write!(f, "shader binding (set = {}, binding = {}) expects {} bytes followed by elements of {} bytes, buffer region has {} bytes", 
+                    set, binding, expected, stride, actual),
+            ErrorCode::BindingSizeMismatch { set, binding, expected, stride: None, actual } => 
+                write!(f, "shader binding (set = {}, binding = {}) expects {} bytes, buffer region has {} bytes", 
+                    set, binding, expected, actual),
+            ErrorCode::BindingNameNotFound(name) => 
+                write!(f, "shader has no binding named {:?}", name),
+            ErrorCode::BindingArrayLengthMissing { set, binding } => 
+                write!(f, "shader binding (set = {}, binding = {}) ends with a runtime array whose length is not given", set, binding),
+            ErrorCode::DescriptorArrayNotDeclared(name) => 
+                write!(f, "binding {:?} is not declared as a descriptor array", name),
+            ErrorCode::DescriptorArrayBindingUndeclared { set, binding } => 
+                write!(f, "shader binding (set = {}, binding = {}) is an array of descriptors not declared by descriptor_array", set, binding),
+            ErrorCode::DescriptorArrayBindingSingle { set, binding } => 
+                write!(f, "shader binding (set = {}, binding = {}) is declared as a descriptor array but takes a single descriptor", set, binding),
+            ErrorCode::DescriptorArrayLengthExceedsCount { set, binding, length, max_count } => 
+                write!(f, "shader binding (set = {}, binding = {}) is an array of {} descriptors, the descriptor array holds up to {}", 
+                    set, binding, length, max_count),
+            ErrorCode::DescriptorArrayCountExceeded { name, count, max_count } => 
+                write!(f, "{} regions exceed the {} descriptors of the array {:?}", count, max_count, name),
+            ErrorCode::DescriptorArrayRegionsInGivenSet { name, set } => 
+                write!(f, "regions of the array {:?} cannot be allocated in set {} which is given a buffer", name, set),
+            ErrorCode::DescriptorSetCountExceedsLimit { count, limit } => 
+                write!(f, "{} descriptor sets exceed maxBoundDescriptorSets {}", count, limit),
+            ErrorCode::DescriptorSetZeroGiven => 
+                write!(f, "set 0 takes the buffer of the pipeline, another buffer given"),
+            ErrorCode::BindingSetLayoutMismatch { set } => 
+                write!(f, "binding set {} does not match the layout of the pipeline", set),
+            ErrorCode::DynamicBindingNotDeclared(name) => 
+                write!(f, "no binding named {:?} is declared to take a dynamic offset", name),
+            ErrorCode::DynamicBindingUnsupported { set, binding } => 
+                write!(f, "shader binding (set = {}, binding = {}) cannot take a dynamic offset in a set with descriptor arrays", set, binding),
+            ErrorCode::DynamicBindingCountExceedsLimit { count, limit } => 
+                write!(f, "{} dynamic bindings exceed maxDescriptorSetStorageBuffersDynamic {}", count, limit),
+            ErrorCode::DynamicOffsetMisaligned { offset, alignment } => 
+                write!(f, "dynamic offset {} is not a multiple of minStorageBufferOffsetAlignment {}", offset, alignment),
+            ErrorCode::DynamicOffsetOutOfRange { set, binding, offset, range, size } => 
+                write!(f, "shader binding (set = {}, binding = {}) sees {} bytes from offset {} beyond its region of {} bytes", 
+                    set, binding, range, offset, size),
+            ErrorCode::SpecializationConstantNotFound(id) => 
+                write!(f, "shader declares no specialization constant with constant_id = {}", id),
+            ErrorCode::SpecializationConstantNameNotFound(name) => 
+                write!(f, "shader declares no specialization constant named {:?}", name),
+            ErrorCode::SpecializationConstantTypeMismatch { id, expected, actual } => 
+                write!(f, "specialization constant {} is a {:?}, {:?} given", id, expected, actual),
+            ErrorCode::SpecializationConstantSizeMismatch { id, expected, actual } => 
+                write!(f, "specialization constant {} expects {} bytes, {} bytes given", id, expected, actual),
+            ErrorCode::PushConstantNameNotFound(name) => 
+                write!(f, "shader push constant block has no member named {:?}", name),
+            ErrorCode::PushConstantMemberSizeMismatch { name, expected, actual } => 
+                write!(f, "push constant {:?} expects {} bytes, {} bytes given", name, expected, actual),
+            ErrorCode::PushConstantTypeMismatch { name, expected, actual } => 
+                write!(f, "push constant {:?} is a {:?}, {:?} given", name, expected, actual),
+            ErrorCode::PushConstantOverlap { name } => 
+                write!(f, "push constants given overlap the member {:?} set by the dispatch", name),
+            ErrorCode::PushConstantSizeMismatch { expected, actual } => 
+                write!(f, "push constant range has {} bytes, {} bytes given", expected, actual),
+            ErrorCode::PushConstantMisaligned { offset, size } => 
+                write!(f, "push constant of {} bytes at offset {} is misaligned", size, offset),
+            ErrorCode::PushConstantSizeExceedsLimit { size, limit } => 
+                write!(f, "push constant range of {} bytes exceeds maxPushConstantsSize of {} bytes", size, limit),
+            ErrorCode::WorkgroupCountExceedsLimit { axis, count, limit } => 
+                write!(f, "{} workgroups along axis {} exceed maxComputeWorkGroupCount of {}", count, axis, limit),
+            ErrorCode::InvocationCountExceedsLimit { axis, count } => 
+                write!(f, "{} invocations along axis {} do not fit in a uint push constant", count, axis),
+            ErrorCode::DispatchIndirectOutOfRange { region, offset } => 
+                write!(f, "buffer region {} holds no dispatch indirect command at offset {}", region, offset),
+            ErrorCode::DispatchBaseNotEnabled =>
+                write!(f, "dispatching with a base requires the pipeline to be built with dispatch_base"),
+            ErrorCode::BindingBufferMismatch => write!(f, "binding belongs to another buffer than the pipeline's"),
+            ErrorCode::PipelineCacheIO(error) => write!(f, "failed to access pipeline cache: {}", error),
+            ErrorCode::ShaderCompile(diagnostics) => {
+                write!(f, "failed to compile shader")?;
+                for diagnostic in diagnostics.iter() {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+// a message reported by the shader compiler
+#[derive(Debug, Clone)]
+pub struct ShaderDiagnostic {
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+}
+
+impl ShaderDiagnostic {
+    pub fn new(file: Option<String>, line: Option<u32>, message: String) -> Self {
+        ShaderDiagnostic {
+            file,
+            line,
+            message,
+        }
+    }
+
+    #[inline]
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    #[inline]
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            (Some(file), None) => write!(f, "{}: {}", file, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -20,6 +243,21 @@ pub struct Error {
     u: Box<ErrorCode>
 }
 
+impl Error {
+    #[inline]
+    pub fn code(&self) -> &ErrorCode {
+        &self.u
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.u)
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl From<VkResult> for Error {
     fn from(code: VkResult) -> Self {
         Error {
@@ -52,4 +290,4 @@ impl VkResult {
             Err(Error::from(self))
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,353 @@
+
+use super::vk::*;
+use super::instance::{PhysicalDevice};
+
+use std::ptr;
+use libc::{c_char, c_void};
+
+// Device features that shaders may depend on, grouped after what defines them.
+// the features of Vulkan 1.1 and 1.2 are only available on devices supporting Vulkan 1.2
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Feature {
+    RobustBufferAccess,
+    PipelineStatisticsQuery,
+    ShaderStorageImageExtendedFormats,
+    ShaderStorageImageReadWithoutFormat,
+    ShaderStorageImageWriteWithoutFormat,
+    ShaderStorageBufferArrayDynamicIndexing,
+    ShaderFloat64,
+    ShaderInt64,
+    ShaderInt16,
+    // Vulkan 1.1
+    StorageBuffer16BitAccess,
+    UniformAndStorageBuffer16BitAccess,
+    StoragePushConstant16,
+    VariablePointersStorageBuffer,
+    VariablePointers,
+    // Vulkan 1.2
+    StorageBuffer8BitAccess,
+    UniformAndStorageBuffer8BitAccess,
+    StoragePushConstant8,
+    ShaderBufferInt64Atomics,
+    ShaderSharedInt64Atomics,
+    ShaderFloat16,
+    ShaderInt8,
+    DescriptorIndexing,
+    ShaderStorageBufferArrayNonUniformIndexing,
+    DescriptorBindingStorageBufferUpdateAfterBind,
+    DescriptorBindingUpdateUnusedWhilePending,
+    DescriptorBindingPartiallyBound,
+    DescriptorBindingVariableDescriptorCount,
+    RuntimeDescriptorArray,
+    ScalarBlockLayout,
+    ShaderSubgroupExtendedTypes,
+    HostQueryReset,
+    TimelineSemaphore,
+    BufferDeviceAddress,
+    VulkanMemoryModel,
+    VulkanMemoryModelDeviceScope,
+    // VK_EXT_subgroup_size_control
+    SubgroupSizeControl,
+    ComputeFullSubgroups,
+}
+
+// the feature structs as chained into vkCreateDevice
+#[derive(Copy, Clone)]
+pub(crate) struct FeatureSet {
+    core: VkPhysicalDeviceFeatures,
+    vulkan11: VkPhysicalDeviceVulkan11Features,
+    vulkan12: VkPhysicalDeviceVulkan12Features,
+    subgroup_size_control: VkPhysicalDeviceSubgroupSizeControlFeaturesEXT,
+    // which of the structs go into the chain
+    has_vulkan12: bool,
+    has_subgroup_size_control: bool,
+}
+
+macro_rules! feature_fields {
+    ($( $feature:ident => $set:ident . $field:ident, )*) => {
+        impl Feature {
+            pub const ALL: &'static [Feature] = &[$( Feature::$feature, )*];
+        }
+
+        impl FeatureSet {
+            fn field(&self, feature: Feature) -> &VkBool32 {
+                match feature {
+                    $( Feature::$feature => &self.$set.$field, )*
+                }
+            }
+
+            fn field_mut(&mut self, feature: Feature) -> &mut VkBool32 {
+                match feature {
+                    $( Feature::$feature => &mut self.$set.$field, )*
+                }
+            }
+        }
+    };
+}
+
+feature_fields! {
+    RobustBufferAccess => core.robustBufferAccess,
+    PipelineStatisticsQuery => core.pipelineStatisticsQuery,
+    ShaderStorageImageExtendedFormats => core.shaderStorageImageExtendedFormats,
+    ShaderStorageImageReadWithoutFormat => core.shaderStorageImageReadWithoutFormat,
+    ShaderStorageImageWriteWithoutFormat => core.shaderStorageImageWriteWithoutFormat,
+    ShaderStorageBufferArrayDynamicIndexing => core.shaderStorageBufferArrayDynamicIndexing,
+    ShaderFloat64 => core.shaderFloat64,
+    ShaderInt64 => core.shaderInt64,
+    ShaderInt16 => core.shaderInt16,
+    StorageBuffer16BitAccess => vulkan11.storageBuffer16BitAccess,
+    UniformAndStorageBuffer16BitAccess => vulkan11.uniformAndStorageBuffer16BitAccess,
+    StoragePushConstant16 => vulkan11.storagePushConstant16,
+    VariablePointersStorageBuffer => vulkan11.variablePointersStorageBuffer,
+    VariablePointers => vulkan11.variablePointers,
+    StorageBuffer8BitAccess => vulkan12.storageBuffer8BitAccess,
+    UniformAndStorageBuffer8BitAccess => vulkan12.uniformAndStorageBuffer8BitAccess,
+    StoragePushConstant8 => vulkan12.storagePushConstant8,
+    ShaderBufferInt64Atomics => vulkan12.shaderBufferInt64Atomics,
+    ShaderSharedInt64Atomics => vulkan12.shaderSharedInt64Atomics,
+    ShaderFloat16 => vulkan12.shaderFloat16,
+    ShaderInt8 => vulkan12.shaderInt8,
+    DescriptorIndexing => vulkan12.descriptorIndexing,
+    ShaderStorageBufferArrayNonUniformIndexing => vulkan12.shaderStorageBufferArrayNonUniformIndexing,
+    DescriptorBindingStorageBufferUpdateAfterBind => vulkan12.descriptorBindingStorageBufferUpdateAfterBind,
+    DescriptorBindingUpdateUnusedWhilePending => vulkan12.descriptorBindingUpdateUnusedWhilePending,
+    DescriptorBindingPartiallyBound => vulkan12.descriptorBindingPartiallyBound,
+    DescriptorBindingVariableDescriptorCount => vulkan12.descriptorBindingVariableDescriptorCount,
+    RuntimeDescriptorArray => vulkan12.runtimeDescriptorArray,
+    ScalarBlockLayout => vulkan12.scalarBlockLayout,
+    ShaderSubgroupExtendedTypes => vulkan12.shaderSubgroupExtendedTypes,
+    HostQueryReset => vulkan12.hostQueryReset,
+    TimelineSemaphore => vulkan12.timelineSemaphore,
+    BufferDeviceAddress => vulkan12.bufferDeviceAddress,
+    VulkanMemoryModel => vulkan12.vulkanMemoryModel,
+    VulkanMemoryModelDeviceScope => vulkan12.vulkanMemoryModelDeviceScope,
+    SubgroupSizeControl => subgroup_size_control.subgroupSizeControl,
+    ComputeFullSubgroups => subgroup_size_control.computeFullSubgroups,
+}
+
+impl FeatureSet {
+    pub(crate) fn none() -> Self {
+        FeatureSet {
+            core: VkPhysicalDeviceFeatures::none(),
+            vulkan11: VkPhysicalDeviceVulkan11Features::none(),
+            vulkan12: VkPhysicalDeviceVulkan12Features::none(),
+            subgroup_size_control: VkPhysicalDeviceSubgroupSizeControlFeaturesEXT::none(),
+            has_vulkan12: false,
+            has_subgroup_size_control: false,
+        }
+    }
+
+    // what the device supports, the structs the device does not know are left out
+    pub(crate) fn supported(physical_device: &PhysicalDevice) -> Self {
+        let mut set = Self::none();
+        unsafe {
+            if Self::is_chained(physical_device) {
+                set.has_vulkan12 = physical_device.properties().apiVersion >= VK_API_VERSION_1_2;
+                set.has_subgroup_size_control = physical_device.supports_extension(VK_EXT_SUBGROUP_SIZE_CONTROL_EXTENSION_NAME);
+                let mut features = set.link();
+                vkGetPhysicalDeviceFeatures2(physical_device.handle(), &mut features);
+                set.core = features.features;
+            } else {
+                vkGetPhysicalDeviceFeatures(physical_device.handle(), &mut set.core);
+            }
+        }
+        set.unlink();
+        set
+    }
+
+    // whether the features are passed through VkPhysicalDeviceFeatures2 instead of pEnabledFeatures
+    pub(crate) fn is_chained(physical_device: &PhysicalDevice) -> bool {
+        physical_device.properties().apiVersion >= VK_API_VERSION_1_1
+    }
+
+    // the features out of the given ones that self contains
+    pub(crate) fn subset<'f>(&self, features: impl Iterator<Item = &'f Feature>) -> Self {
+        let mut set = Self::none();
+        set.has_vulkan12 = self.has_vulkan12;
+        features.filter(|&&v| self.contains(v))
+            .for_each(|&v| set.insert(v));
+        // the extension is only enabled when any of its features are
+        set.has_subgroup_size_control = set.contains(Feature::SubgroupSizeControl) 
+            || set.contains(Feature::ComputeFullSubgroups);
+        set
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, feature: Feature) -> bool {
+        *self.field(feature) == VK_TRUE
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, feature: Feature) {
+        *self.field_mut(feature) = VK_TRUE;
+    }
+
+    pub(crate) fn features(&self) -> Vec<Feature> {
+        Feature::ALL.iter()
+            .cloned()
+            .filter(|&v| self.contains(v))
+            .collect()
+    }
+
+    #[inline]
+    pub(crate) fn core(&self) -> &VkPhysicalDeviceFeatures {
+        &self.core
+    }
+
+    // the device extensions the chained structs come from
+    pub(crate) fn extension_names(&self) -> Vec<*const c_char> {
+        let mut names = vec![];
+        if self.has_subgroup_size_control {
+            names.push(VK_EXT_SUBGROUP_SIZE_CONTROL_EXTENSION_NAME.as_ptr() as *const c_char);
+        }
+        names
+    }
+
+    // links the structs in place, only valid as long as self is not moved
+    pub(crate) fn link(&mut self) -> VkPhysicalDeviceFeatures2 {
+        let mut next: *mut c_void = ptr::null_mut();
+        if self.has_subgroup_size_control {
+            self.subgroup_size_control.pNext = next;
+            next = &mut self.subgroup_size_control as *mut _ as *mut c_void;
+        }
+        if self.has_vulkan12 {
+            self.vulkan12.pNext = next;
+            self.vulkan11.pNext = &mut self.vulkan12 as *mut _ as *mut c_void;
+            next = &mut self.vulkan11 as *mut _ as *mut c_void;
+        }
+        VkPhysicalDeviceFeatures2::new(self.core, next)
+    }
+
+    pub(crate) fn unlink(&mut self) {
+        self.vulkan11.pNext = ptr::null_mut();
+        self.vulkan12.pNext = ptr::null_mut();
+        self.subgroup_size_control.pNext = ptr::null_mut();
+    }
+}
+
+// Subgroup operations a device may support, see `SubgroupProperties::supports`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SubgroupOperation {
+    Basic,
+    Vote,
+    Arithmetic,
+    Ballot,
+    Shuffle,
+    ShuffleRelative,
+    Clustered,
+    Quad,
+}
+
+impl SubgroupOperation {
+    fn flag(self) -> VkSubgroupFeatureFlagBits {
+        match self {
+            SubgroupOperation::Basic => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_BASIC_BIT,
+            SubgroupOperation::Vote => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_VOTE_BIT,
+            SubgroupOperation::Arithmetic => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_ARITHMETIC_BIT,
+            SubgroupOperation::Ballot => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_BALLOT_BIT,
+            SubgroupOperation::Shuffle => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_SHUFFLE_BIT,
+            SubgroupOperation::ShuffleRelative => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_SHUFFLE_RELATIVE_BIT,
+            SubgroupOperation::Clustered => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_CLUSTERED_BIT,
+            SubgroupOperation::Quad => VkSubgroupFeatureFlagBits::VK_SUBGROUP_FEATURE_QUAD_BIT,
+        }
+    }
+}
+
+// VkPhysicalDeviceSubgroupProperties along with the sizes VK_EXT_subgroup_size_control allows
+#[derive(Debug, Copy, Clone)]
+pub struct SubgroupProperties {
+    size: u32,
+    stages: VkShaderStageFlags,
+    operations: VkSubgroupFeatureFlags,
+    quad_operations_in_all_stages: bool,
+    size_control: Option<SubgroupSizeControl>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SubgroupSizeControl {
+    min_size: u32,
+    max_size: u32,
+    max_compute_workgroup_subgroups: u32,
+    required_size_stages: VkShaderStageFlags,
+}
+
+impl SubgroupProperties {
+    // devices below Vulkan 1.1 run subgroups of one invocation without any operations
+    pub(crate) fn query(physical_device: &PhysicalDevice) -> Self {
+        if !FeatureSet::is_chained(physical_device) {
+            return SubgroupProperties {
+                size: 1,
+                stages: 0,
+                operations: 0,
+                quad_operations_in_all_stages: false,
+                size_control: None,
+            }
+        }
+        let has_size_control = physical_device.supports_extension(VK_EXT_SUBGROUP_SIZE_CONTROL_EXTENSION_NAME);
+        let mut size_control = VkPhysicalDeviceSubgroupSizeControlPropertiesEXT::new();
+        let next = if has_size_control {
+            &mut size_control as *mut _ as *mut c_void
+        } else {
+            ptr::null_mut()
+        };
+        let mut subgroup = VkPhysicalDeviceSubgroupProperties::new(next);
+        let mut properties = VkPhysicalDeviceProperties2::new(&mut subgroup as *mut _ as *mut c_void);
+        unsafe {
+            vkGetPhysicalDeviceProperties2(physical_device.handle(), &mut properties);
+        }
+        let size_control = if has_size_control {
+            let size_control = SubgroupSizeControl {
+                min_size: size_control.minSubgroupSize,
+                max_size: size_control.maxSubgroupSize,
+                max_compute_workgroup_subgroups: size_control.maxComputeWorkgroupSubgroups,
+                required_size_stages: size_control.requiredSubgroupSizeStages,
+            };
+            Some(size_control)
+        } else {
+            None
+        };
+        SubgroupProperties {
+            size: subgroup.subgroupSize,
+            stages: subgroup.supportedStages,
+            operations: subgroup.supportedOperations,
+            quad_operations_in_all_stages: subgroup.quadOperationsInAllStages == VK_TRUE,
+            size_control,
+        }
+    }
+
+    // the default number of invocations in a subgroup
+    #[inline]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    // whether compute shaders can use subgroup operations at all
+    #[inline]
+    pub fn supports_compute(&self) -> bool {
+        (self.stages & VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32) != 0
+    }
+
+    #[inline]
+    pub fn supports(&self, operation: SubgroupOperation) -> bool {
+        (self.operations & operation.flag() as u32) != 0
+    }
+
+    #[inline]
+    pub fn quad_operations_in_all_stages(&self) -> bool {
+        self.quad_operations_in_all_stages
+    }
+
+    // (min, max) sizes a compute pipeline can require, 
+    // none unless the device supports VK_EXT_subgroup_size_control for compute shaders
+    pub fn size_range(&self) -> Option<(u32, u32)> {
+        self.size_control
+            .filter(|v| (v.required_size_stages & VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT as u32) != 0)
+            .map(|v| (v.min_size, v.max_size))
+    }
+
+    // the number of subgroups a workgroup can hold at most with VK_EXT_subgroup_size_control
+    pub fn max_compute_workgroup_subgroups(&self) -> Option<u32> {
+        self.size_control
+            .map(|v| v.max_compute_workgroup_subgroups)
+    }
+}
@@ -0,0 +1,158 @@
+#![allow(non_camel_case_types)]
+
+use std::fmt;
+
+// IEEE 754 half precision float as laid out for float16_t in shaders,
+// e.g. `BindingArray<f16>` with `Feature::StorageBuffer16BitAccess`
+#[repr(transparent)]
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct f16(u16);
+
+impl f16 {
+    pub const ZERO: f16 = f16(0);
+    pub const ONE: f16 = f16(0x3c00);
+
+    #[inline]
+    pub const fn from_bits(bits: u16) -> Self {
+        f16(bits)
+    }
+
+    #[inline]
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    // rounds to the nearest, ties to even
+    pub fn from_f32(value: f32) -> Self {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x7f_ffff;
+        if exponent == 0xff {
+            // NaN stays quiet
+            let nan = if mantissa != 0 { 0x0200 } else { 0 };
+            return f16(sign | 0x7c00 | nan)
+        }
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1f {
+            return f16(sign | 0x7c00)
+        }
+        // the bits shifted out of the mantissa decide the rounding
+        let (half, shift) = if exponent <= 0 {
+            if exponent < -10 {
+                return f16(sign)
+            }
+            // subnormal with the implicit leading bit
+            let shift = (14 - exponent) as u32;
+            ((mantissa | 0x80_0000) >> shift, shift)
+        } else {
+            (((exponent as u32) << 10) | (mantissa >> 13), 13)
+        };
+        let full = if exponent <= 0 { mantissa | 0x80_0000 } else { mantissa };
+        let rest = full & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        // a carry into the exponent rounds up to the next binade or to infinity
+        let half = if rest > halfway || (rest == halfway && (half & 1) == 1) { half + 1 } else { half };
+        f16(sign | half as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exponent = ((self.0 >> 10) & 0x1f) as u32;
+        let mantissa = (self.0 & 0x3ff) as u32;
+        let bits = match exponent {
+            // subnormals are exact in f32
+            0 => (mantissa as f32 / (1 << 24) as f32).to_bits(),
+            0x1f => 0x7f80_0000 | (mantissa << 13),
+            _ => ((exponent + 127 - 15) << 23) | (mantissa << 13),
+        };
+        f32::from_bits(sign | bits)
+    }
+}
+
+impl From<f32> for f16 {
+    fn from(value: f32) -> Self {
+        f16::from_f32(value)
+    }
+}
+
+impl From<f16> for f32 {
+    fn from(value: f16) -> Self {
+        value.to_f32()
+    }
+}
+
+impl fmt::Debug for f16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_f32(), f)
+    }
+}
+
+impl fmt::Display for f16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f32(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_values() {
+        let values = [(0.0, 0x0000), (-0.0, 0x8000), (1.0, 0x3c00), (-2.0, 0xc000), (0.5, 0x3800), (65504.0, 0x7bff)];
+        for &(value, bits) in values.iter() {
+            assert_eq!(f16::from_f32(value).to_bits(), bits);
+            assert_eq!(f16::from_bits(bits).to_f32(), value);
+        }
+        assert_eq!(f16::from_f32(1.0), f16::ONE);
+    }
+
+    #[test]
+    fn rounds_to_nearest_even() {
+        let ulp = 2.0f32.powi(-10);
+        // halfway between 1.0 and the next, 1.0 is even
+        assert_eq!(f16::from_f32(1.0 + ulp / 2.0).to_bits(), 0x3c00);
+        // halfway between the next and the one after, the latter is even
+        assert_eq!(f16::from_f32(1.0 + ulp * 1.5).to_bits(), 0x3c02);
+        assert_eq!(f16::from_f32(1.0 + ulp * 0.75).to_bits(), 0x3c01);
+        // rounds up to the next binade
+        assert_eq!(f16::from_f32(2.0 - ulp / 4.0).to_bits(), 0x4000);
+        assert_eq!(f16::from_f32(65519.0).to_bits(), 0x7bff);
+        assert_eq!(f16::from_f32(65520.0).to_bits(), 0x7c00);
+    }
+
+    #[test]
+    fn subnormals() {
+        let min = 2.0f32.powi(-24);
+        assert_eq!(f16::from_f32(min).to_bits(), 0x0001);
+        assert_eq!(f16::from_bits(0x0001).to_f32(), min);
+        assert_eq!(f16::from_bits(0x83ff).to_f32(), -min * 1023.0);
+        assert_eq!(f16::from_f32(min / 2.0).to_bits(), 0x0000);
+        assert_eq!(f16::from_f32(min * 1.5).to_bits(), 0x0002);
+        assert_eq!(f16::from_f32(-min / 4.0).to_bits(), 0x8000);
+        // the largest subnormal rounds up to the smallest normal
+        assert_eq!(f16::from_f32(2.0f32.powi(-14) - min / 4.0).to_bits(), 0x0400);
+    }
+
+    #[test]
+    fn infinity_and_nan() {
+        assert_eq!(f16::from_f32(f32::INFINITY).to_bits(), 0x7c00);
+        assert_eq!(f16::from_f32(f32::NEG_INFINITY).to_bits(), 0xfc00);
+        assert_eq!(f16::from_f32(1.0e10).to_bits(), 0x7c00);
+        assert_eq!(f16::from_bits(0xfc00).to_f32(), f32::NEG_INFINITY);
+        assert_eq!(f16::from_f32(f32::NAN).to_bits() & 0x7e00, 0x7e00);
+        assert!(f16::from_bits(0x7e00).to_f32().is_nan());
+    }
+
+    #[test]
+    fn round_trip() {
+        for bits in 0..=u16::MAX {
+            let value = f16::from_bits(bits);
+            if value.to_f32().is_nan() {
+                continue
+            }
+            assert_eq!(f16::from_f32(value.to_f32()).to_bits(), bits);
+        }
+    }
+}
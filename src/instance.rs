@@ -1,10 +1,8 @@
 
 use crate::vk::*;
 use crate::error::Result;
-use crate::error::ErrorCode;
 
 use std::ptr;
-use std::mem;
 use std::ffi::{CStr, CString};
 use std::mem::MaybeUninit;
 use std::sync::Arc;
@@ -37,6 +35,10 @@ impl Instance {
     }
 }
 
+// the handle is only externally synchronized on destruction, which Drop makes exclusive
+unsafe impl Send for Instance {}
+unsafe impl Sync for Instance {}
+
 impl Drop for Instance {
     fn drop(&mut self) {
         log_debug!("Drop Instance");
@@ -84,6 +86,10 @@ pub struct PhysicalDevice {
     instance: Arc<Instance>,
 }
 
+// physical devices are only queried, never mutated
+unsafe impl Send for PhysicalDevice {}
+unsafe impl Sync for PhysicalDevice {}
+
 impl PhysicalDevice {
     pub fn new(device: VkPhysicalDevice, instance: &Arc<Instance>) -> Self {
         PhysicalDevice { handle: device, instance: Arc::clone(instance) }
@@ -107,6 +113,35 @@ impl PhysicalDevice {
         }
     }
 
+    // whether the device offers the extension, e.g. `VK_EXT_SUBGROUP_SIZE_CONTROL_EXTENSION_NAME`
+    pub fn supports_extension(&self, name: &[u8]) -> bool {
+        let name = match CStr::from_bytes_with_nul(name) {
+            Ok(name) => name,
+            Err(_) => return false,
+        };
+        unsafe {
+            let mut count = MaybeUninit::<u32>::zeroed();
+            // obtain count
+            let result = vkEnumerateDeviceExtensionProperties(self.handle, ptr::null(), count.as_mut_ptr(), ptr::null_mut())
+                .into_result();
+            if result.is_err() {
+                return false
+            }
+            // obtain items
+            let size: usize = count.assume_init() as usize;
+            let mut extensions: Vec<VkExtensionProperties> = Vec::with_capacity(size);
+            extensions.resize_with(size, || std::mem::zeroed());
+            let result = vkEnumerateDeviceExtensionProperties(self.handle, ptr::null(), count.as_mut_ptr(), extensions.as_mut_ptr())
+                .into_result();
+            if result.is_err() {
+                return false
+            }
+            extensions.iter()
+                .take(count.assume_init() as usize)
+                .any(|v| CStr::from_ptr(v.extensionName.as_ptr()) == name)
+        }
+    }
+
     pub fn queue_families(&self) -> Result<Vec<QueueFamily>> {
         unsafe {
             let mut count = MaybeUninit::<u32>::zeroed();
@@ -150,4 +185,10 @@ impl QueueFamily {
     pub fn is_compute(&self) -> bool {
         self.property.has_compute_queue_bit()
     }
+
+    // zero when the queues of the family write no timestamps
+    #[inline]
+    pub fn timestamp_valid_bits(&self) -> u32 {
+        self.property.timestampValidBits
+    }
 }
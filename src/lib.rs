@@ -8,16 +8,34 @@ pub mod error;
 mod vk;
 mod instance;
 mod device;
+mod features;
 mod dispatch;
+mod constants;
+mod reflection;
+mod profiler;
 
 mod context;
 mod buffer;
 mod view;
+mod half;
 mod pipeline;
+#[cfg(feature = "glsl")]
+mod compiler;
 
-pub use context::{Context};
+pub use context::{Context, ContextBuilder};
+pub use features::{Feature, SubgroupProperties, SubgroupOperation};
+pub use half::{f16};
+pub use device::{ShaderModule};
 pub use buffer::{Buffer};
 pub use view::{BufferLayout, BindingVariant, BindingArray, BindingValue};
 pub use view::{BufferBindingView, BufferView, BufferViewBuilder};
-pub use pipeline::{Pipeline, PipelineBuilder, DispatchBuilder};
-pub use dispatch::{ConstantEntry, CommandDispatch};
+pub use pipeline::{Pipeline, PipelineBuilder, DispatchBuilder, ReflectedLayout, Tile};
+pub use dispatch::{ConstantEntry, CommandDispatch, DispatchIndirectCommand, BindingSet, PipelineRegistryStats};
+pub use constants::{ConstantValue, Specialization, PushConstants};
+pub use profiler::{ProfiledOperation, ProfileRecord, OperationProfile, PipelineProfile, ProfileReport};
+#[cfg(feature = "glsl")]
+pub use compiler::{GlslSource, TargetEnv};
+pub use reflection::{ShaderInterface, EntryPoint, ExecutionModel, DescriptorBinding, DescriptorType, DescriptorCount};
+pub use reflection::{BlockLayout, BlockMember, SpecializationConstant, ScalarType, capability_name};
+#[doc(hidden)]
+pub use reflection::{spirv_word_count, spirv_words};
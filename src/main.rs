@@ -33,7 +33,7 @@ fn fibonacci(num_elements: usize) {
     binding.update_array_copying(&v);
     // runs the computation specifying how many invocations of 
     // the shader performed.
-    pipeline.dispatch(num_elements);
+    pipeline.dispatch(num_elements).unwrap();
     // retrieves back data from the GPU
     binding.fetch_array_copying(&mut v);
     println!("{:?}", v);
@@ -94,7 +94,7 @@ fn connected_component_labeling() {
     let binding = buffer_view.binding();
     binding.update_array_copying(&table);
     // column
-    column.dispatch(dim.0);
+    column.dispatch(dim.0).unwrap();
     // merge
     {
         let mut step_index = 0;
@@ -112,7 +112,7 @@ fn connected_component_labeling() {
         }
     }
     // relabel
-    relabel.dispatch(len);
+    relabel.dispatch(len).unwrap();
     binding.fetch_array_copying(&mut table);
     // output
     dump(&table, dim.0);
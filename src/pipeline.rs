@@ -2,14 +2,26 @@
 
 use super::context::{Context};
 use super::buffer::{Buffer};
+use super::view::{BufferBindingView};
 
-use super::instance::{Instance};
-use super::device::{Device, DeviceBuilder, CommandPool, ShaderModule, ShaderModuleSource};
-use super::dispatch::{StagingBuffer, ComputePipeline, CommandDispatch, WorkgroupCount, ConstantEntry};
+use super::device::{Device, ShaderModule, ShaderModuleSource};
+use super::dispatch::{ComputePipeline, CommandDispatch, WorkgroupCount, ConstantEntry};
+use super::dispatch::{DispatchCommand, DispatchIndirect, PipelineOptions, DescriptorArray, DescriptorLayout};
+use super::dispatch::{DescriptorSetLayout, BindingSet, DynamicBinding, PipelineKey};
+use super::features::{Feature};
+use super::constants::{Specialization, PushConstants, ConstantValue, PUSH_CONSTANT_ALIGNMENT};
+use super::reflection::{ShaderInterface, DescriptorBinding, DescriptorCount};
+#[cfg(feature = "glsl")]
+use super::compiler::{GlslSource, TargetEnv};
 
 use super::error::Result;
-use std::sync::Arc;
-use std::marker::PhantomData;
+use super::error::ErrorCode;
+use super::error::Error;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::mem;
+use std::path::PathBuf;
+use std::time::{SystemTime, Instant, Duration};
+use std::convert::TryFrom;
 
 #[macro_export]
 macro_rules! constants {
@@ -33,88 +45,1193 @@ macro_rules! constants {
     };
 }
 
-pub struct PipelineBuilder<'a, ShaderType, SpecializationType> {
+// embeds a SPIR-V file as `&'static [u32]`, the path is relative to the current file.
+// fails to compile unless the file is a SPIR-V module with a compute entry point
+#[macro_export]
+macro_rules! include_spirv {
+    ($path:expr) => {
+        {
+            const BYTES: &[u8] = include_bytes!($path);
+            static WORDS: [u32; $crate::spirv_word_count(BYTES)] = $crate::spirv_words(BYTES);
+            &WORDS as &'static [u32]
+        }
+    };
+}
+
+pub struct PipelineBuilder<ShaderType, SpecializationType, BufferType> {
     shader: ShaderType,
     specialization: SpecializationType,
-    buffer: &'a Arc<Buffer>,
+    buffer: BufferType,
+    entry_point: String,
+    push_constant_size: Option<usize>,
+    hot_reload: bool,
+    options: PipelineOptions,
 }
 
-impl<'a> PipelineBuilder<'a, (), ()> {
+impl<'a> PipelineBuilder<(), (), &'a Arc<Buffer>> {
     pub fn new(buffer: &'a Arc<Buffer>) -> Self {
         PipelineBuilder {
             shader: (),
             specialization: (),
             buffer: buffer,
+            entry_point: DEFAULT_ENTRY_POINT.to_string(),
+            push_constant_size: None,
+            hot_reload: false,
+            options: PipelineOptions::default(),
+        }
+    }
+
+    // allocates a buffer laid out after the bindings the shader declares
+    pub fn from_shader(context: &'a Arc<Context>, filename: impl Into<String>) 
+        -> PipelineBuilder<ShaderModuleSource, (), ReflectedLayout<'a>> {
+        PipelineBuilder {
+            shader: ShaderModuleSource::from_file(filename),
+            specialization: (),
+            buffer: ReflectedLayout::new(context),
+            entry_point: DEFAULT_ENTRY_POINT.to_string(),
+            push_constant_size: None,
+            hot_reload: false,
+            options: PipelineOptions::default(),
+        }
+    }
+
+    // same as `from_shader` but with a module shared among pipelines
+    pub fn from_shader_module(context: &'a Arc<Context>, shader_module: &Arc<ShaderModule>) 
+        -> PipelineBuilder<Arc<ShaderModule>, (), ReflectedLayout<'a>> {
+        PipelineBuilder {
+            shader: Arc::clone(shader_module),
+            specialization: (),
+            buffer: ReflectedLayout::new(context),
+            entry_point: DEFAULT_ENTRY_POINT.to_string(),
+            push_constant_size: None,
+            hot_reload: false,
+            options: PipelineOptions::default(),
         }
     }
 }
 
-impl<'a, SpecializationType> PipelineBuilder<'a, (), SpecializationType> {
-    pub fn shader(self, filename: impl Into<String>) -> PipelineBuilder<'a, ShaderModuleSource, SpecializationType> {
+impl<SpecializationType, BufferType> PipelineBuilder<(), SpecializationType, BufferType> {
+    pub fn shader(self, filename: impl Into<String>) -> PipelineBuilder<ShaderModuleSource, SpecializationType, BufferType> {
         PipelineBuilder {
             shader: ShaderModuleSource::from_file(filename),
             specialization: self.specialization,
             buffer: self.buffer,
+            entry_point: self.entry_point,
+            push_constant_size: self.push_constant_size,
+            hot_reload: self.hot_reload,
+            options: self.options,
         }
     }
 
-    pub fn shader_bytes(self, bytes: Vec<u8>) -> PipelineBuilder<'a, ShaderModuleSource, SpecializationType> {
+    pub fn shader_bytes(self, bytes: Vec<u8>) -> PipelineBuilder<ShaderModuleSource, SpecializationType, BufferType> {
         PipelineBuilder {
             shader: ShaderModuleSource::from_bytes(bytes),
             specialization: self.specialization,
             buffer: self.buffer,
+            entry_point: self.entry_point,
+            push_constant_size: self.push_constant_size,
+            hot_reload: self.hot_reload,
+            options: self.options,
+        }
+    }
+
+    // takes the words as they are, e.g. embedded by `include_spirv!`
+    pub fn shader_words(self, words: &[u32]) -> PipelineBuilder<&[u32], SpecializationType, BufferType> {
+        PipelineBuilder {
+            shader: words,
+            specialization: self.specialization,
+            buffer: self.buffer,
+            entry_point: self.entry_point,
+            push_constant_size: self.push_constant_size,
+            hot_reload: self.hot_reload,
+            options: self.options,
+        }
+    }
+
+    // shares a module loaded once, e.g. by `Context::shader_module` or `Pipeline::shader_module`
+    pub fn shader_module(self, shader_module: &Arc<ShaderModule>) -> PipelineBuilder<Arc<ShaderModule>, SpecializationType, BufferType> {
+        PipelineBuilder {
+            shader: Arc::clone(shader_module),
+            specialization: self.specialization,
+            buffer: self.buffer,
+            entry_point: self.entry_point,
+            push_constant_size: self.push_constant_size,
+            hot_reload: self.hot_reload,
+            options: self.options,
+        }
+    }
+}
+
+#[cfg(feature = "glsl")]
+impl<SpecializationType, BufferType> PipelineBuilder<(), SpecializationType, BufferType> {
+    // compiles the GLSL compute shader on build
+    pub fn glsl_source(self, source: impl Into<String>) -> PipelineBuilder<GlslSource, SpecializationType, BufferType> {
+        self.glsl(GlslSource::from_text(source))
+    }
+
+    // compiles the GLSL compute shader file on build, 
+    // `#include "..."` is resolved next to the file
+    pub fn glsl_file(self, filename: impl Into<String>) -> PipelineBuilder<GlslSource, SpecializationType, BufferType> {
+        self.glsl(GlslSource::from_file(filename))
+    }
+
+    pub fn glsl(self, source: GlslSource) -> PipelineBuilder<GlslSource, SpecializationType, BufferType> {
+        PipelineBuilder {
+            shader: source,
+            specialization: self.specialization,
+            buffer: self.buffer,
+            entry_point: self.entry_point,
+            push_constant_size: self.push_constant_size,
+            hot_reload: self.hot_reload,
+            options: self.options,
         }
     }
 }
 
-impl<'a, ShaderType> PipelineBuilder<'a, ShaderType, ()> {
-    pub fn specialization(self, constants: Vec<ConstantEntry>) 
-        -> PipelineBuilder<'a, ShaderType, Vec<ConstantEntry>> {
+#[cfg(feature = "glsl")]
+impl<SpecializationType, BufferType> PipelineBuilder<GlslSource, SpecializationType, BufferType> {
+    // same as `#define name value` at the beginning of the source
+    pub fn define(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.shader = self.shader.define(name, Some(value.to_string()));
+        self
+    }
+
+    pub fn include_dir(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.shader = self.shader.include_dir(path);
+        self
+    }
+
+    // Vulkan 1.1 by default
+    pub fn target_env(mut self, target_env: TargetEnv) -> Self {
+        self.shader = self.shader.target_env(target_env);
+        self
+    }
+}
+
+impl<ShaderType, BufferType> PipelineBuilder<ShaderType, (), BufferType> {
+    // takes either `constants!` in the order of constant_id or a `Specialization`
+    pub fn specialization(self, constants: impl Into<Specialization>) 
+        -> PipelineBuilder<ShaderType, Specialization, BufferType> {
         PipelineBuilder {
             shader: self.shader,
-            specialization: constants,
+            specialization: constants.into(),
             buffer: self.buffer,
+            entry_point: self.entry_point,
+            push_constant_size: self.push_constant_size,
+            hot_reload: self.hot_reload,
+            options: self.options,
         }
     }
 }
 
-impl<'a> PipelineBuilder<'a, ShaderModuleSource, ()> {
+impl<ShaderType, SpecializationType, BufferType> PipelineBuilder<ShaderType, SpecializationType, BufferType> {
+    // the compute entry point to run, `main` by default
+    pub fn entry_point(mut self, name: impl Into<String>) -> Self {
+        self.entry_point = name.into();
+        self
+    }
+
+    // the size of the push constant range, 
+    // the size of the push constant block the shader declares by default
+    pub fn push_constant_size(mut self, size: usize) -> Self {
+        self.push_constant_size = Some(size);
+        self
+    }
+
+    // rebuilds the pipeline on the next dispatch once the shader file changes, 
+    // meant for development. GLSL files are also rebuilt when a file they `#include` changes.
+    // shaders not loaded from a file are not watched
+    pub fn hot_reload(mut self) -> Self {
+        self.hot_reload = true;
+        self
+    }
+
+    // allows `DispatchBuilder::base`, which some implementations run slower for
+    pub fn dispatch_base(mut self) -> Self {
+        self.options.dispatch_base = true;
+        self
+    }
+
+    // the specialization constant with the id is given the subgroup size the pipeline runs with,
+    // the required one if any, otherwise the default size of the device
+    pub fn subgroup_size_constant(mut self, id: u32) -> Self {
+        self.options.subgroup_size_constant = Some(id);
+        self
+    }
+
+    // runs subgroups of the size, which needs `Feature::SubgroupSizeControl`
+    pub fn required_subgroup_size(mut self, size: u32) -> Self {
+        self.options.required_subgroup_size = Some(size);
+        self
+    }
+
+    // no partially filled subgroups, which needs `Feature::ComputeFullSubgroups`.
+    // local_size_x must be a multiple of the subgroup size
+    pub fn full_subgroups(mut self) -> Self {
+        self.options.full_subgroups = true;
+        self
+    }
+
+    // the named binding holds an array of up to `max_count` buffer regions, e.g. `buffer Data { ... } inputs[]`,
+    // bound by `Pipeline::bind_array` instead of the region of the same index. 
+    // needs `Feature::DescriptorBindingPartiallyBound` and `Feature::DescriptorBindingStorageBufferUpdateAfterBind`
+    pub fn descriptor_array(mut self, name: impl Into<String>, max_count: u32) -> Self {
+        self.options.descriptor_arrays.push((name.into(), max_count));
+        self
+    }
+
+    // the named binding sees `range` bytes of its region from the offset each dispatch gives, 
+    // see `DispatchBuilder::dynamic_offset`. the binding cannot share its set with descriptor arrays
+    pub fn dynamic_binding(mut self, name: impl Into<String>, range: usize) -> Self {
+        self.options.dynamic_bindings.push((name.into(), range));
+        self
+    }
+
+    // binds the regions of the buffer to the bindings of the set in order, 
+    // e.g. set 0 for the data kept across batches and set 1 for the data of each batch.
+    // set 0 takes the buffer the pipeline is built with, giving it another one fails the build
+    pub fn descriptor_set(mut self, set: u32, buffer: &Arc<Buffer>) -> Self {
+        self.options.set_buffers.retain(|(v, _)| *v != set);
+        self.options.set_buffers.push((set, Arc::clone(buffer)));
+        self
+    }
+}
+
+impl<'a, ShaderType, SpecializationType> PipelineBuilder<ShaderType, SpecializationType, ReflectedLayout<'a>> {
+    // the number of elements of the runtime array that ends the named binding
+    pub fn array_length(mut self, name: impl Into<String>, count: usize) -> Self {
+        self.buffer.array_lengths.push((name.into(), count));
+        self
+    }
+
+    // allocates regions of the sizes after the ones of the bindings, 
+    // bound to the named descriptor array on build
+    pub fn array_regions(mut self, name: impl Into<String>, sizes: Vec<usize>) -> Self {
+        self.buffer.array_regions.push((name.into(), sizes));
+        self
+    }
+}
+
+impl<ShaderType: ShaderSource> PipelineBuilder<ShaderType, (), &Arc<Buffer>> {
+    pub fn build(self) -> Result<Arc<Pipeline>> {
+        let watch = self.watch();
+        let shader_module = Self::shader_module(self.shader, watch.as_ref(), self.buffer.context())?;
+        Pipeline::new(self.buffer, &shader_module, &self.entry_point, Specialization::new(), self.push_constant_size, self.options, vec![], watch)
+    }
+}
+
+impl<ShaderType: ShaderSource> PipelineBuilder<ShaderType, Specialization, &Arc<Buffer>> {
+    pub fn build(self) -> Result<Arc<Pipeline>> {
+        let watch = self.watch();
+        let shader_module = Self::shader_module(self.shader, watch.as_ref(), self.buffer.context())?;
+        Pipeline::new(self.buffer, &shader_module, &self.entry_point, self.specialization, self.push_constant_size, self.options, vec![], watch)
+    }
+}
+
+impl<'a, ShaderType: ShaderSource> PipelineBuilder<ShaderType, (), ReflectedLayout<'a>> {
     pub fn build(self) -> Result<Arc<Pipeline>> {
-        Pipeline::new(self.buffer, self.shader, vec![])
+        let watch = self.watch();
+        let shader_module = Self::shader_module(self.shader, watch.as_ref(), self.buffer.context)?;
+        let interface = shader_module.interface().validate_entry_point(&self.entry_point)?;
+        let mut options = self.options;
+        let (buffer, bound_arrays) = self.buffer.build(&interface, &mut options)?;
+        Pipeline::new(&buffer, &shader_module, &self.entry_point, Specialization::new(), self.push_constant_size, options, bound_arrays, watch)
     }
 }
 
-impl<'a> PipelineBuilder<'a, ShaderModuleSource, Vec<ConstantEntry>> {
+impl<'a, ShaderType: ShaderSource> PipelineBuilder<ShaderType, Specialization, ReflectedLayout<'a>> {
     pub fn build(self) -> Result<Arc<Pipeline>> {
-        Pipeline::new(self.buffer, self.shader, self.specialization)
+        let watch = self.watch();
+        let shader_module = Self::shader_module(self.shader, watch.as_ref(), self.buffer.context)?;
+        let interface = shader_module.interface().validate_entry_point(&self.entry_point)?;
+        let mut options = self.options;
+        let (buffer, bound_arrays) = self.buffer.build(&interface, &mut options)?;
+        Pipeline::new(&buffer, &shader_module, &self.entry_point, self.specialization, self.push_constant_size, options, bound_arrays, watch)
+    }
+}
+
+impl<ShaderType: ShaderSource, SpecializationType, BufferType> PipelineBuilder<ShaderType, SpecializationType, BufferType> {
+    // taken before loading so that changes made meanwhile are picked up
+    fn watch(&self) -> Option<ShaderWatch> {
+        if self.hot_reload {
+            self.shader.watch()
+        } else {
+            None
+        }
+    }
+
+    // loaded through the watch if any, which records the files the module is built from
+    fn shader_module(shader: ShaderType, watch: Option<&ShaderWatch>, context: &Arc<Context>) -> Result<Arc<ShaderModule>> {
+        match watch {
+            Some(watch) => (watch.load)(context),
+            None => shader.shader_module(context),
+        }
+    }
+}
+
+const DEFAULT_ENTRY_POINT: &str = "main";
+
+// the uint push constant a dispatch split along x sets to the index of its first invocation
+const SPLIT_BASE_MEMBER: &str = "base_x";
+
+// the uint push constants `dispatch_invocations` sets to the invocation counts
+const INVOCATION_COUNT_MEMBERS: [&str; 3] = ["count_x", "count_y", "count_z"];
+
+// where a pipeline gets its shader module from, either loaded on build or shared
+pub trait ShaderSource {
+    fn shader_module(self, context: &Arc<Context>) -> Result<Arc<ShaderModule>>;
+
+    // the file to reload the module from when it changes
+    fn watch(&self) -> Option<ShaderWatch> {
+        None
+    }
+}
+
+impl ShaderSource for ShaderModuleSource {
+    fn shader_module(self, context: &Arc<Context>) -> Result<Arc<ShaderModule>> {
+        ShaderModule::new(context.device(), self)
+    }
+
+    fn watch(&self) -> Option<ShaderWatch> {
+        match self {
+            ShaderModuleSource::FilePath(filename) => {
+                let filename = filename.clone();
+                Some(ShaderWatch::new(filename.clone(), move |context| {
+                    ShaderModule::new(context.device(), ShaderModuleSource::from_file(filename.clone()))
+                }))
+            },
+            ShaderModuleSource::Bytes(_) => None,
+        }
+    }
+}
+
+type ShaderLoader = dyn Fn(&Arc<Context>) -> Result<Arc<ShaderModule>> + Send + Sync;
+
+// the files a shader source includes with their modification times as of the last load
+pub(crate) type IncludedFiles = Arc<Mutex<Vec<(PathBuf, Option<SystemTime>)>>>;
+
+// how often dispatches look at the modification time of a watched file
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+// a shader file reloaded when its modification time changes
+pub struct ShaderWatch {
+    path: PathBuf,
+    modified: Mutex<Option<SystemTime>>,
+    load: Box<ShaderLoader>,
+    // recorded by each load, e.g. the headers of GLSL
+    includes: IncludedFiles,
+    // when a dispatch last looked at the file
+    checked: Mutex<Option<Instant>>,
+    // the error the last reload on a dispatch failed with
+    last_error: Mutex<Option<Arc<Error>>>,
+}
+
+impl ShaderWatch {
+    pub(crate) fn new(path: impl Into<PathBuf>, 
+        load: impl Fn(&Arc<Context>) -> Result<Arc<ShaderModule>> + Send + Sync + 'static) -> Self {
+        let path = path.into();
+        let modified = Self::modified(&path).ok();
+        ShaderWatch {
+            path,
+            modified: Mutex::new(modified),
+            load: Box::new(load),
+            includes: IncludedFiles::default(),
+            checked: Mutex::new(None),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    // the files the loader records the source including
+    #[cfg(feature = "glsl")]
+    pub(crate) fn with_includes(mut self, includes: IncludedFiles) -> Self {
+        self.includes = includes;
+        self
+    }
+
+    fn is_include_modified(&self) -> bool {
+        self.includes.lock().unwrap().iter()
+            .any(|(path, modified)| Self::modified(path).ok() != *modified)
+    }
+
+    // whether a dispatch should look at the file again, at most once per interval
+    fn is_due(&self) -> bool {
+        let mut checked = self.checked.lock().unwrap();
+        let now = Instant::now();
+        match *checked {
+            Some(v) if now.duration_since(v) < RELOAD_CHECK_INTERVAL => false,
+            _ => {
+                *checked = Some(now);
+                true
+            },
+        }
+    }
+
+    fn modified(path: &PathBuf) -> Result<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|v| v.modified())
+            .map_err(|v| ErrorCode::ShaderLoadIO(v).into())
+    }
+}
+
+impl ShaderSource for &[u32] {
+    fn shader_module(self, context: &Arc<Context>) -> Result<Arc<ShaderModule>> {
+        ShaderModule::from_words(context.device(), self)
+    }
+}
+
+impl ShaderSource for Arc<ShaderModule> {
+    fn shader_module(self, _: &Arc<Context>) -> Result<Arc<ShaderModule>> {
+        Ok(self)
+    }
+}
+
+// the smallest region allocated for a binding, 
+// also given to binding numbers the shader leaves unused so that regions stay positional
+const MIN_REGION_SIZE: usize = 4;
+
+// the regions bound to each descriptor array by its name
+type BoundArrays = Vec<(String, Vec<usize>)>;
+
+pub struct ReflectedLayout<'a> {
+    context: &'a Arc<Context>,
+    array_lengths: Vec<(String, usize)>,
+    array_regions: BoundArrays,
+}
+
+impl<'a> ReflectedLayout<'a> {
+    fn new(context: &'a Arc<Context>) -> Self {
+        ReflectedLayout {
+            context,
+            array_lengths: vec![],
+            array_regions: vec![],
+        }
+    }
+
+    // the buffer of set 0 with the regions bound to the descriptor arrays, 
+    // the buffers of the other sets the shader uses are added to the options unless given
+    fn build(self, interface: &ShaderInterface, options: &mut PipelineOptions) -> Result<(Arc<Buffer>, BoundArrays)> {
+        // the buffer laid out for set 0 is the one of the pipeline
+        if options.set_buffers.iter().any(|(set, _)| *set == 0) {
+            return Err(ErrorCode::DescriptorSetZeroGiven.into())
+        }
+        let mut array_lengths = Vec::<(u32, u32, usize)>::new();
+        for (name, count) in self.array_lengths.iter() {
+            let binding = interface.binding_by_name(name)
+                .ok_or_else(|| ErrorCode::BindingNameNotFound(name.clone()))?;
+            array_lengths.push((binding.set(), binding.binding(), *count));
+        }
+        let set_count = interface.bindings().iter()
+            .map(|v| v.set() + 1)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut set_region_sizes = vec![];
+        for set in 0..set_count {
+            // sets given a buffer are laid out by it, sets the shader skips are bound empty
+            let is_given = options.set_buffers.iter().any(|(v, _)| *v == set);
+            let is_used = set == 0 || interface.bindings().iter().any(|v| v.set() == set);
+            if is_given || !is_used {
+                set_region_sizes.push(None);
+                continue
+            }
+            set_region_sizes.push(Some(Self::region_sizes(interface, set, &array_lengths)?));
+        }
+        let context = self.context;
+        let mut bound_arrays = vec![];
+        for (name, sizes) in self.array_regions.into_iter() {
+            let set = interface.binding_by_name(&name)
+                .ok_or_else(|| ErrorCode::BindingNameNotFound(name.clone()))?
+                .set();
+            let region_sizes = match set_region_sizes[set as usize].as_mut() {
+                Some(region_sizes) => region_sizes,
+                None => return Err(ErrorCode::DescriptorArrayRegionsInGivenSet { name, set }.into()),
+            };
+            let first = region_sizes.len();
+            region_sizes.extend(sizes.iter().map(|v| (*v).max(MIN_REGION_SIZE)));
+            bound_arrays.push((name, (first..region_sizes.len()).collect()));
+        }
+        let mut buffers = set_region_sizes.into_iter()
+            .enumerate()
+            .filter_map(|(set, region_sizes)| region_sizes.map(|v| (set as u32, Buffer::new(context, v))));
+        let (_, buffer) = buffers.next().unwrap();
+        options.set_buffers.extend(buffers);
+        Ok((buffer, bound_arrays))
+    }
+
+    // buffer regions are bound to the bindings of the set in order
+    fn region_sizes(interface: &ShaderInterface, set: u32, array_lengths: &[(u32, u32, usize)]) -> Result<Vec<usize>> {
+        let bindings: Vec<&DescriptorBinding> = interface.bindings().iter()
+            .filter(|v| v.set() == set)
+            .collect();
+        let region_count = bindings.iter()
+            .map(|v| v.binding() as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut region_sizes = vec![MIN_REGION_SIZE; region_count];
+        for binding in bindings {
+            // descriptor arrays leave their regions unused
+            if binding.descriptor_count() != DescriptorCount::Single {
+                continue
+            }
+            let block = match binding.block() {
+                Some(block) => block,
+                None => continue,
+            };
+            let array_length = array_lengths.iter()
+                .find(|(v, index, _)| *v == set && *index == binding.binding())
+                .map(|(_, _, count)| *count);
+            let size = match (block.runtime_array_stride(), array_length) {
+                (Some(stride), Some(count)) => block.size() + stride * count,
+                (Some(_), None) => {
+                    let binding = binding.binding();
+                    return Err(ErrorCode::BindingArrayLengthMissing { set, binding }.into())
+                },
+                (None, _) => block.size(),
+            };
+            region_sizes[binding.binding() as usize] = size.max(MIN_REGION_SIZE);
+        }
+        Ok(region_sizes)
     }
 }
 
 pub struct Pipeline {
     buffer: Arc<Buffer>,
+    entry_point: String,
+    specialization: Specialization,
+    push_constant_size: Option<usize>,
+    options: PipelineOptions,
+    // the regions bound to the descriptor arrays by name, bound again on reload
+    bound_arrays: Mutex<BoundArrays>,
+    shader: RwLock<PipelineShader>,
+    watch: Option<ShaderWatch>,
+}
+
+// what gets replaced when the shader is reloaded
+struct PipelineShader {
     shader_module: Arc<ShaderModule>,
+    interface: Arc<ShaderInterface>,
     compute_pipeline: Arc<ComputePipeline>,
+    // bound unless a dispatch gives others, in the order of set numbers
+    binding_sets: Vec<Arc<BindingSet>>,
+    local_size: [u32; 3],
+}
+
+impl PipelineShader {
+    // the binding sets of the pipeline with the given ones in place of the same set numbers
+    fn binding_sets(&self, binding_sets: &[Arc<BindingSet>]) -> Vec<Arc<BindingSet>> {
+        self.binding_sets.iter()
+            .map(|v| {
+                let binding_set = binding_sets.iter()
+                    .find(|given| given.set() == v.set())
+                    .unwrap_or(v);
+                Arc::clone(binding_set)
+            })
+            .collect()
+    }
+
+    #[inline]
+    fn binding_set(&self, set: u32) -> Option<&Arc<BindingSet>> {
+        self.binding_sets.get(set as usize)
+    }
 }
 
 impl Pipeline {
-    fn new(buffer: &Arc<Buffer>, shader: ShaderModuleSource, spec_constants: Vec<ConstantEntry>) -> Result<Arc<Self>> {
-        let context = buffer.context();
-        let device = context.device();
-        let staging_buffer = buffer.staging_buffer();
-        let shader_module = ShaderModule::new(device, shader)?;
-        let compute_pipeline = ComputePipeline::new(staging_buffer, &shader_module, spec_constants);
+    #[allow(clippy::too_many_arguments)]
+    fn new(buffer: &Arc<Buffer>, 
+        shader_module: &Arc<ShaderModule>, 
+        entry_point: &str, 
+        specialization: Specialization,
+        push_constant_size: Option<usize>,
+        options: PipelineOptions,
+        bound_arrays: BoundArrays,
+        watch: Option<ShaderWatch>) -> Result<Arc<Self>> {
+        let shader = Self::shader(buffer, shader_module, entry_point, specialization.clone(), push_constant_size, &options, &bound_arrays)?;
         let pipeline = Pipeline {
             buffer: Arc::clone(buffer),
-            shader_module: shader_module,
-            compute_pipeline: compute_pipeline,
+            entry_point: entry_point.to_string(),
+            specialization,
+            push_constant_size,
+            options,
+            bound_arrays: Mutex::new(bound_arrays),
+            shader: RwLock::new(shader),
+            watch,
         };
         Ok(Arc::new(pipeline))
     }
 
-    pub fn dispatch(&self, count_x: usize) {
-        let count = WorkgroupCount { x: count_x as u32, y: 1, z: 1 };
-        let dispatch = CommandDispatch::new(&self.compute_pipeline, count, vec![]);
+    fn shader(buffer: &Arc<Buffer>, 
+        shader_module: &Arc<ShaderModule>, 
+        entry_point: &str, 
+        specialization: Specialization,
+        push_constant_size: Option<usize>,
+        options: &PipelineOptions,
+        bound_arrays: &[(String, Vec<usize>)]) -> Result<PipelineShader> {
+        let device = buffer.context().device();
+        // checks what we provide against what the entry point uses
+        let interface = shader_module.interface().validate_entry_point(entry_point)?;
+        if let Some((name, _)) = bound_arrays.iter().find(|(name, _)| !options.descriptor_arrays.iter().any(|(v, _)| v == name)) {
+            return Err(ErrorCode::DescriptorArrayNotDeclared(name.clone()).into())
+        }
+        let set_count = Self::set_count(device, &interface, options)?;
+        let set_layouts = (0..set_count)
+            .map(|set| Self::set_layout(buffer, &interface, options, bound_arrays, set))
+            .collect::<Result<Vec<(&Arc<Buffer>, DescriptorLayout)>>>()?;
+        let dynamic_count = set_layouts.iter()
+            .map(|(_, layout)| layout.dynamic_descriptor_count())
+            .sum::<u32>();
+        let dynamic_limit = device.physical_device().properties().limits.maxDescriptorSetStorageBuffersDynamic;
+        if dynamic_count > dynamic_limit {
+            return Err(ErrorCode::DynamicBindingCountExceedsLimit { count: dynamic_count, limit: dynamic_limit }.into())
+        }
+        let specialization = match options.subgroup_size_constant {
+            Some(id) => {
+                let size = options.required_subgroup_size
+                    .unwrap_or_else(|| device.subgroup_properties().size());
+                specialization.subgroup_size(id, size)
+            },
+            None => specialization,
+        };
+        let spec_constants = specialization.resolve(&interface)?;
+        let push_constant_size = Self::push_constant_size(buffer.context(), &interface, push_constant_size)?;
+        let local_size = Self::specialized_local_size(&interface, &spec_constants);
+        Self::validate_subgroup_size(device, options, local_size)?;
+        let key = PipelineKey {
+            code: shader_module.code().clone(),
+            entry_point: entry_point.to_string(),
+            spec_constants: spec_constants.iter().map(|(id, v)| (*id, v.bytes().to_vec())).collect(),
+            push_constant_size,
+            dispatch_base: options.dispatch_base,
+            required_subgroup_size: options.required_subgroup_size,
+            full_subgroups: options.full_subgroups,
+            set_layouts: set_layouts.iter().map(|(_, layout)| layout.clone()).collect(),
+        };
+        let context = buffer.context();
+        let compute_pipeline = context.pipeline_registry().get_or_create(key, || {
+            let descriptor_set_layouts = set_layouts.iter()
+                .map(|(_, layout)| DescriptorSetLayout::new(device, layout.clone()))
+                .collect();
+            ComputePipeline::new(device, descriptor_set_layouts, shader_module, context.pipeline_cache(), 
+                entry_point, &spec_constants, push_constant_size, options)
+        })?;
+        let mut binding_sets = vec![];
+        for (set, ((set_buffer, _), layout)) in set_layouts.iter().zip(compute_pipeline.set_layouts().iter()).enumerate() {
+            let set = set as u32;
+            let arrays: Vec<(u32, Vec<usize>)> = bound_arrays.iter()
+                .filter_map(|(name, regions)| {
+                    interface.binding_by_name(name)
+                        .filter(|v| v.set() == set)
+                        .map(|v| (v.binding(), regions.clone()))
+                })
+                .collect();
+            binding_sets.push(BindingSet::new(set, layout, context.descriptor_allocator(), set_buffer.staging_buffer(), &arrays)?);
+        }
+        // the module compiled first is kept when the pipeline is shared
+        let shader = PipelineShader {
+            shader_module: Arc::clone(compute_pipeline.shader_module()),
+            interface: Arc::new(interface),
+            compute_pipeline: compute_pipeline,
+            binding_sets,
+            local_size,
+        };
+        Ok(shader)
+    }
+
+    // rebuilds the pipeline if the shader file or a file it includes has changed since it was last loaded, 
+    // the current pipeline is kept when the new one fails to build.
+    // returns whether the pipeline has been rebuilt
+    pub fn reload(&self) -> Result<bool> {
+        let watch = match &self.watch {
+            Some(watch) => watch,
+            None => return Ok(false),
+        };
+        // held while rebuilding so that concurrent dispatches rebuild only once
+        let mut modified = watch.modified.lock().unwrap();
+        let current = ShaderWatch::modified(&watch.path)?;
+        if *modified == Some(current) && !watch.is_include_modified() {
+            return Ok(false)
+        }
+        // a broken file is not retried until it changes again
+        *modified = Some(current);
+        let shader_module = (watch.load)(self.buffer.context())?;
+        // held until replaced so that the regions bound meanwhile are not lost
+        let bound_arrays = self.bound_arrays.lock().unwrap();
+        let shader = Self::shader(&self.buffer, &shader_module, &self.entry_point, 
+            self.specialization.clone(), self.push_constant_size, &self.options, &bound_arrays)?;
+        *self.shader.write().unwrap() = shader;
+        *watch.last_error.lock().unwrap() = None;
+        log_debug!("Reloaded {:?}", watch.path);
+        Ok(true)
+    }
+
+    // dispatches go on with the current pipeline when reloading fails, 
+    // the error is kept for `last_reload_error`
+    fn reload_before_dispatch(&self) {
+        let watch = match &self.watch {
+            Some(watch) if watch.is_due() => watch,
+            _ => return,
+        };
+        if let Err(error) = self.reload() {
+            log_debug!("Failed to reload {:?}: {}", watch.path, error);
+            *watch.last_error.lock().unwrap() = Some(Arc::new(error));
+        }
+    }
+
+    // the error a reload on a dispatch failed with, until a reload succeeds
+    pub fn last_reload_error(&self) -> Option<Arc<Error>> {
+        self.watch.as_ref()
+            .and_then(|v| v.last_error.lock().unwrap().clone())
+    }
+
+    fn current(&self) -> RwLockReadGuard<'_, PipelineShader> {
+        self.shader.read().unwrap()
+    }
+
+    // the local size the shader declares with the specialization constants given for it applied
+    fn specialized_local_size(interface: &ShaderInterface, spec_constants: &[(u32, ConstantEntry)]) -> [u32; 3] {
+        let entry_point = &interface.entry_points()[0];
+        let mut local_size = entry_point.local_size();
+        for (index, id) in entry_point.local_size_ids().iter().enumerate() {
+            let constant = id.and_then(|id| spec_constants.iter().find(|(v, _)| *v == id));
+            if let Some((_, constant)) = constant {
+                let bytes = constant.bytes();
+                local_size[index] = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+        }
+        local_size
+    }
+
+    // up to the highest set the shader uses or is given a buffer
+    fn set_count(device: &Device, interface: &ShaderInterface, options: &PipelineOptions) -> Result<u32> {
+        if options.set_buffers.iter().any(|(set, _)| *set == 0) {
+            return Err(ErrorCode::DescriptorSetZeroGiven.into())
+        }
+        let count = interface.bindings().iter()
+            .map(|v| v.set() + 1)
+            .chain(options.set_buffers.iter().map(|(set, _)| set + 1))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let limit = device.physical_device().properties().limits.maxBoundDescriptorSets;
+        if count > limit {
+            return Err(ErrorCode::DescriptorSetCountExceedsLimit { count, limit }.into())
+        }
+        Ok(count)
+    }
+
+    // the buffer bound to the set unless a dispatch binds another one
+    fn set_buffer_of<'b>(buffer: &'b Arc<Buffer>, options: &'b PipelineOptions, set: u32) -> Option<&'b Arc<Buffer>> {
+        if set == 0 {
+            return Some(buffer)
+        }
+        options.set_buffers.iter()
+            .find(|(v, _)| *v == set)
+            .map(|(_, buffer)| buffer)
+    }
+
+    // the buffer bound to the set by default with the layout of the set
+    fn set_layout<'b>(buffer: &'b Arc<Buffer>, 
+        interface: &ShaderInterface, 
+        options: &'b PipelineOptions, 
+        bound_arrays: &[(String, Vec<usize>)],
+        set: u32) -> Result<(&'b Arc<Buffer>, DescriptorLayout)> {
+        let used = interface.bindings().iter()
+            .find(|v| v.set() == set);
+        let set_buffer = match (Self::set_buffer_of(buffer, options, set), used) {
+            (Some(set_buffer), _) => set_buffer,
+            (None, Some(binding)) => 
+                return Err(ErrorCode::BindingSetUnsupported { set, binding: binding.binding() }.into()),
+            // sets the shader skips are bound empty
+            (None, None) => buffer,
+        };
+        let layout = Self::descriptor_layout(set, set_buffer, interface, options, bound_arrays)?;
+        interface.validate_bindings(set, set_buffer.region_sizes(), &layout.arrays, &layout.dynamic_bindings)?;
+        Ok((set_buffer, layout))
+    }
+
+    // the bindings of the set take the regions of the same index except for the descriptor arrays
+    fn descriptor_layout(set: u32,
+        buffer: &Arc<Buffer>, 
+        interface: &ShaderInterface, 
+        options: &PipelineOptions, 
+        bound_arrays: &[(String, Vec<usize>)]) -> Result<DescriptorLayout> {
+        let device = buffer.context().device();
+        let binding_count = interface.bindings().iter()
+            .filter(|v| v.set() == set)
+            .map(|v| v.binding() + 1)
+            .max()
+            .unwrap_or(0);
+        let mut arrays = vec![];
+        for (name, max_count) in options.descriptor_arrays.iter() {
+            let binding = interface.binding_by_name(name)
+                .ok_or_else(|| ErrorCode::BindingNameNotFound(name.to_string()))?;
+            if binding.set() != set {
+                continue
+            }
+            for &feature in [Feature::DescriptorBindingPartiallyBound, Feature::DescriptorBindingStorageBufferUpdateAfterBind].iter() {
+                if !device.is_enabled(feature) {
+                    return Err(ErrorCode::FeatureNotEnabled(feature).into())
+                }
+            }
+            let regions = bound_arrays.iter()
+                .find(|(v, _)| v == name)
+                .map(|(_, regions)| regions.clone())
+                .unwrap_or_default();
+            Self::validate_array_regions(buffer, binding, name, *max_count, &regions)?;
+            // only the highest binding of the set can take a variable count
+            let variable_count = binding.descriptor_count() == DescriptorCount::Runtime && 
+                binding.binding() + 1 == binding_count &&
+                device.is_enabled(Feature::DescriptorBindingVariableDescriptorCount);
+            let array = DescriptorArray {
+                binding: binding.binding(),
+                max_count: *max_count,
+                variable_count,
+            };
+            arrays.push(array);
+        }
+        let mut dynamic_bindings = vec![];
+        for (name, range) in options.dynamic_bindings.iter() {
+            let binding = interface.binding_by_name(name)
+                .ok_or_else(|| ErrorCode::BindingNameNotFound(name.to_string()))?;
+            if binding.set() != set {
+                continue
+            }
+            // update after bind pools take no dynamic descriptors
+            if !arrays.is_empty() {
+                return Err(ErrorCode::DynamicBindingUnsupported { set, binding: binding.binding() }.into())
+            }
+            dynamic_bindings.push(DynamicBinding { binding: binding.binding(), range: *range });
+        }
+        dynamic_bindings.sort_by_key(|v| v.binding);
+        let region_bindings = (0..binding_count)
+            .filter(|&v| (v as usize) < buffer.region_count())
+            .filter(|&v| !arrays.iter().any(|array| array.binding == v) && !dynamic_bindings.iter().any(|dynamic| dynamic.binding == v))
+            .collect();
+        Ok(DescriptorLayout { region_bindings, arrays, dynamic_bindings })
+    }
+
+    // the regions of the buffer fit the descriptor array
+    fn validate_array_regions(buffer: &Arc<Buffer>, 
+        binding: &DescriptorBinding, 
+        name: &str, 
+        max_count: u32, 
+        regions: &[usize]) -> Result<()> {
+        if regions.len() > max_count as usize {
+            return Err(ErrorCode::DescriptorArrayCountExceeded { name: name.to_string(), count: regions.len(), max_count }.into())
+        }
+        for &region in regions.iter() {
+            let size = *buffer.region_sizes().get(region)
+                .ok_or_else(|| ErrorCode::BindingNotFound { set: binding.set(), binding: binding.binding() })?;
+            binding.validate_region_size(size)?;
+        }
+        Ok(())
+    }
+
+    // VK_EXT_subgroup_size_control leaves these to the application
+    fn validate_subgroup_size(device: &Device, options: &PipelineOptions, local_size: [u32; 3]) -> Result<()> {
+        let properties = device.subgroup_properties();
+        if let Some(size) = options.required_subgroup_size {
+            if !device.is_enabled(Feature::SubgroupSizeControl) {
+                return Err(ErrorCode::FeatureNotEnabled(Feature::SubgroupSizeControl).into())
+            }
+            let (min, max) = properties.size_range().unwrap_or((0, 0));
+            if !size.is_power_of_two() || size < min || size > max {
+                return Err(ErrorCode::SubgroupSizeUnsupported { size, min, max }.into())
+            }
+            let invocations = local_size.iter().product::<u32>();
+            let limit = properties.max_compute_workgroup_subgroups().unwrap_or(0) * size;
+            if invocations > limit {
+                return Err(ErrorCode::WorkgroupSizeExceedsSubgroupLimit { invocations, limit }.into())
+            }
+        }
+        if options.full_subgroups {
+            if !device.is_enabled(Feature::ComputeFullSubgroups) {
+                return Err(ErrorCode::FeatureNotEnabled(Feature::ComputeFullSubgroups).into())
+            }
+            // any size up to the max may be chosen unless one is required
+            let size = options.required_subgroup_size
+                .or_else(|| properties.size_range().map(|(_, max)| max))
+                .unwrap_or(properties.size());
+            if !local_size[0].is_multiple_of(size) {
+                return Err(ErrorCode::LocalSizeNotMultipleOfSubgroupSize { local_size_x: local_size[0], subgroup_size: size }.into())
+            }
+        }
+        Ok(())
+    }
+
+    fn push_constant_size(context: &Arc<Context>, interface: &ShaderInterface, size: Option<usize>) -> Result<usize> {
+        let block_size = interface.push_constants()
+            .map(|v| v.size())
+            .unwrap_or(0);
+        // a block ending with a 16-bit member declares a range padded to 4 bytes
+        let size = size.unwrap_or(block_size.next_multiple_of(PUSH_CONSTANT_ALIGNMENT));
+        if size < block_size {
+            return Err(ErrorCode::PushConstantSizeMismatch { expected: block_size, actual: size }.into())
+        }
+        if !size.is_multiple_of(PUSH_CONSTANT_ALIGNMENT) {
+            return Err(ErrorCode::PushConstantMisaligned { offset: 0, size }.into())
+        }
+        let limit = context.device().physical_device().properties().limits.maxPushConstantsSize as usize;
+        if size > limit {
+            return Err(ErrorCode::PushConstantSizeExceedsLimit { size, limit }.into())
+        }
+        Ok(size)
+    }
+
+    // the resources used by the entry point of the pipeline
+    pub fn interface(&self) -> Arc<ShaderInterface> {
+        Arc::clone(&self.current().interface)
+    }
+
+    pub fn shader_module(&self) -> Arc<ShaderModule> {
+        Arc::clone(&self.current().shader_module)
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &Arc<Buffer> {
+        &self.buffer
+    }
+
+    // the buffer bound to the set unless a dispatch binds another one, 
+    // either given by `PipelineBuilder::descriptor_set` or allocated after the bindings of the set
+    pub fn set_buffer(&self, set: u32) -> Option<&Arc<Buffer>> {
+        Self::set_buffer_of(&self.buffer, &self.options, set)
+    }
+
+    // the binding the shader declares with the name, in the buffer of its set.
+    // see `ShaderInterface::binding_by_name` for how the name is looked up
+    pub fn binding(&self, name: &str) -> Option<Arc<BufferBindingView>> {
+        let interface = self.interface();
+        let binding = interface.binding_by_name(name)?;
+        let index = binding.binding() as usize;
+        let buffer = self.set_buffer(binding.set())?;
+        let is_single = binding.descriptor_count() == DescriptorCount::Single;
+        if index < buffer.region_count() && is_single {
+            Some(BufferBindingView::new(index, buffer))
+        } else {
+            None
+        }
+    }
+
+    // the regions bound to the descriptor array, in the order of its elements
+    pub fn array_bindings(&self, name: &str) -> Option<Vec<Arc<BufferBindingView>>> {
+        let interface = self.interface();
+        let buffer = self.set_buffer(interface.binding_by_name(name)?.set())?;
+        let bound_arrays = self.bound_arrays.lock().unwrap();
+        let (_, regions) = bound_arrays.iter().find(|(v, _)| v == name)?;
+        let bindings = regions.iter()
+            .map(|&index| BufferBindingView::new(index, buffer))
+            .collect();
+        Some(bindings)
+    }
+
+    // the regions of the buffer bound to the set in place of the pipeline's own, see `DispatchBuilder::bind_set`.
+    // the buffer is laid out the same as `set_buffer`, e.g. one for each batch of data.
+    // the descriptor arrays of the set are left unbound
+    pub fn binding_set(&self, set: u32, buffer: &Arc<Buffer>) -> Result<Arc<BindingSet>> {
+        let current = self.current();
+        let layout = current.binding_set(set)
+            .ok_or(ErrorCode::BindingSetLayoutMismatch { set })?
+            .layout();
+        let descriptor_layout = layout.layout();
+        current.interface.validate_bindings(set, buffer.region_sizes(), &descriptor_layout.arrays, &descriptor_layout.dynamic_bindings)?;
+        if let Some(&binding) = descriptor_layout.region_bindings.iter().find(|&&v| v as usize >= buffer.region_count()) {
+            return Err(ErrorCode::BindingNotFound { set, binding }.into())
+        }
+        BindingSet::new(set, layout, self.buffer.context().descriptor_allocator(), buffer.staging_buffer(), &[])
+    }
+
+    // binds the regions of the buffer of its set to the descriptor array from its first element, 
+    // the elements past them keep what was bound before and must not be accessed by the shader.
+    // waits for the dispatches in flight, the dispatches built before run with the new regions as well
+    pub fn bind_array<VariantType>(&self, name: &str, bindings: &[Arc<BufferBindingView<VariantType>>]) -> Result<()> {
+        let max_count = self.options.descriptor_arrays.iter()
+            .find(|(v, _)| v == name)
+            .map(|(_, count)| *count)
+            .ok_or_else(|| ErrorCode::DescriptorArrayNotDeclared(name.to_string()))?;
+        let regions: Vec<usize> = bindings.iter()
+            .map(|v| v.region_index())
+            .collect();
+        let mut bound_arrays = self.bound_arrays.lock().unwrap();
+        let current = self.current();
+        let binding = current.interface.binding_by_name(name)
+            .ok_or_else(|| ErrorCode::BindingNameNotFound(name.to_string()))?;
+        let (set, buffer) = (binding.set(), self.set_buffer(binding.set()));
+        let buffer = buffer.ok_or(ErrorCode::BindingSetUnsupported { set, binding: binding.binding() })?;
+        if bindings.iter().any(|v| !Arc::ptr_eq(v.buffer(), buffer)) {
+            return Err(ErrorCode::BindingBufferMismatch.into())
+        }
+        Self::validate_array_regions(buffer, binding, name, max_count, &regions)?;
+        current.binding_set(set)
+            .unwrap()
+            .bind_array(binding.binding(), &regions);
+        bound_arrays.retain(|(v, _)| v != name);
+        bound_arrays.push((name.to_string(), regions));
+        Ok(())
+    }
+
+    // the offsets of the dynamic bindings in the order of the sets bound, zero unless given by name
+    fn dynamic_offsets(&self, 
+        shader: &PipelineShader, 
+        binding_sets: &[Arc<BindingSet>], 
+        offsets: &[(String, usize)]) -> Result<Vec<u32>> {
+        let mut given = vec![];
+        for (name, offset) in offsets.iter() {
+            let binding = shader.interface.binding_by_name(name)
+                .filter(|_| self.options.dynamic_bindings.iter().any(|(v, _)| v == name))
+                .ok_or_else(|| ErrorCode::DynamicBindingNotDeclared(name.clone()))?;
+            given.push((binding.set(), binding.binding(), *offset));
+        }
+        let limits = &self.buffer.context().device().physical_device().properties().limits;
+        let alignment = limits.minStorageBufferOffsetAlignment as usize;
+        let mut dynamic_offsets = vec![];
+        for binding_set in shader.binding_sets(binding_sets).iter() {
+            let set = binding_set.set();
+            for dynamic in binding_set.layout().layout().dynamic_bindings.iter() {
+                let binding = dynamic.binding;
+                let offset = given.iter()
+                    .find(|(v, index, _)| *v == set && *index == binding)
+                    .map(|(_, _, offset)| *offset)
+                    .unwrap_or(0);
+                if offset % alignment.max(1) != 0 {
+                    return Err(ErrorCode::DynamicOffsetMisaligned { offset, alignment }.into())
+                }
+                let size = binding_set.region_size(binding);
+                let range = dynamic.range;
+                if offset + range > size || offset > u32::MAX as usize {
+                    return Err(ErrorCode::DynamicOffsetOutOfRange { set, binding, offset, range, size }.into())
+                }
+                dynamic_offsets.push(offset as u32);
+            }
+        }
+        Ok(dynamic_offsets)
+    }
+
+    // the size of a workgroup as the pipeline runs it
+    pub fn local_size(&self) -> [u32; 3] {
+        self.current().local_size
+    }
+
+    // the number of workgroups covering the invocations, 
+    // each rounded up to a multiple of the local size
+    pub fn workgroup_count(&self, x: usize, y: usize, z: usize) -> Result<(usize, usize, usize)> {
+        let local_size = self.local_size();
+        let mut count = [x, y, z];
+        for (axis, count) in count.iter_mut().enumerate() {
+            let size = local_size[axis].max(1) as usize;
+            *count = count.div_ceil(size);
+            if *count > u32::MAX as usize {
+                let limit = u32::MAX;
+                return Err(ErrorCode::WorkgroupCountExceedsLimit { axis, count: *count, limit }.into())
+            }
+        }
+        Ok((count[0], count[1], count[2]))
+    }
+
+    fn workgroup_count_limit(&self) -> [u32; 3] {
+        self.buffer.context().device().physical_device().properties().limits.maxComputeWorkGroupCount
+    }
+
+    // the workgroups of a dispatch. a count beyond maxComputeWorkGroupCount[0] is split into 
+    // several dispatches along x if the shader declares the uint push constant `base_x`, 
+    // which each of them sets to the index of its first invocation.
+    // a 1D count is folded into rows of a 2D grid otherwise, see `WorkgroupCount::fold_x`
+    fn dispatch_command(&self, shader: &PipelineShader, count: WorkgroupCount) -> Result<DispatchCommand> {
+        let limit = self.workgroup_count_limit();
+        if count.x <= limit[0] {
+            return Ok(count.check(limit)?.into())
+        }
+        let push_constant_size = shader.compute_pipeline.push_constant_size();
+        let offset = match PushConstants::find::<u32>(&shader.interface, SPLIT_BASE_MEMBER, push_constant_size)? {
+            Some(offset) => offset,
+            None => return Ok(count.fold_x(limit[0]).check(limit)?.into()),
+        };
+        WorkgroupCount { x: limit[0], ..count }.check(limit)?;
+        // the index of the last invocation must fit in `base_x`
+        let local_size_x = shader.local_size[0].max(1);
+        let invocation_limit = u32::MAX / local_size_x;
+        if count.x > invocation_limit {
+            return Err(ErrorCode::WorkgroupCountExceedsLimit { axis: 0, count: count.x as usize, limit: invocation_limit }.into())
+        }
+        let parts = count.split_x(limit[0]).into_iter()
+            .map(|(base, count)| (base * local_size_x, count))
+            .collect();
+        Ok(DispatchCommand::Split { parts, offset: offset as u32 })
+    }
+
+    // `count_x` is the number of workgroups, 
+    // which equals the number of invocations only when `local_size_x = 1`.
+    // counts beyond maxComputeWorkGroupCount[0] are split into dispatches if the shader takes `base_x`,
+    // where it finds its index as `base_x + gl_GlobalInvocationID.x`. 
+    // they are folded into rows of a 2D grid otherwise, see `WorkgroupCount::fold_x`
+    pub fn dispatch(&self, count_x: usize) -> Result<()> {
+        self.dispatch_3d(count_x, 1, 1)
+    }
+
+    pub fn dispatch_2d(&self, count_x: usize, count_y: usize) -> Result<()> {
+        self.dispatch_3d(count_x, count_y, 1)
+    }
+
+    pub fn dispatch_3d(&self, count_x: usize, count_y: usize, count_z: usize) -> Result<()> {
+        self.reload_before_dispatch();
+        let count = WorkgroupCount::new(count_x, count_y, count_z)?;
+        let current = self.current();
+        let command = self.dispatch_command(&current, count)?;
+        let dynamic_offsets = self.dynamic_offsets(&current, &[], &[])?;
+        let dispatch = CommandDispatch::new(&current.compute_pipeline, command, vec![], current.binding_sets(&[]), &dynamic_offsets);
+        dispatch.dispatch();
+        Ok(())
+    }
+
+    // one dispatch for each tile of the grid of workgroups,
+    // e.g. submitted one by one to spread the work over time.
+    // the pipeline must be built with `PipelineBuilder::dispatch_base`
+    pub fn tiled_dispatches(self: &Arc<Self>,
+        count: (usize, usize, usize),
+        tile_size: (usize, usize, usize)) -> Result<Vec<Arc<CommandDispatch>>> {
+        Tile::split(count, tile_size).into_iter()
+            .map(|tile| {
+                DispatchBuilder::new(self)
+                    .workgroup_count(tile.count.0, tile.count.1, tile.count.2)
+                    .base(tile.base.0, tile.base.1, tile.base.2)
+                    .build()
+            })
+            .collect()
+    }
+
+    // runs at least the number of invocations in each dimension.
+    // the invocations beyond them are left to the shader to skip, 
+    // which finds the counts in the uint push constants `count_x`, `count_y` and `count_z` it declares
+    pub fn dispatch_invocations(&self, x: usize, y: usize, z: usize) -> Result<()> {
+        self.dispatch_invocations_with(x, y, z, PushConstants::new())
+    }
+
+    // `dispatch_invocations` along with push constants, which must not overlap the counts
+    pub fn dispatch_invocations_with(&self, x: usize, y: usize, z: usize, push_constants: impl Into<PushConstants>) -> Result<()> {
+        self.reload_before_dispatch();
+        let (count_x, count_y, count_z) = self.workgroup_count(x, y, z)?;
+        let current = self.current();
+        let push_constant_size = current.compute_pipeline.push_constant_size();
+        let mut placed = push_constants.into().place_entries(&current.interface, push_constant_size)?;
+        for (axis, (&name, &count)) in INVOCATION_COUNT_MEMBERS.iter().zip([x, y, z].iter()).enumerate() {
+            let offset = match PushConstants::find::<u32>(&current.interface, name, push_constant_size)? {
+                Some(offset) => offset,
+                None => continue,
+            };
+            let end = offset + mem::size_of::<u32>();
+            if placed.iter().any(|(v, entry)| *v < end && offset < *v + entry.size()) {
+                return Err(ErrorCode::PushConstantOverlap { name: name.to_string() }.into())
+            }
+            let count = u32::try_from(count)
+                .map_err(|_| ErrorCode::InvocationCountExceedsLimit { axis, count })?;
+            placed.push((offset, count.to_entry()));
+        }
+        let push_constants = PushConstants::pack(&placed);
+        let count = WorkgroupCount::new(count_x, count_y, count_z)?;
+        let command = self.dispatch_command(&current, count)?;
+        let dynamic_offsets = self.dynamic_offsets(&current, &[], &[])?;
+        let dispatch = CommandDispatch::new(&current.compute_pipeline, command, push_constants, current.binding_sets(&[]), &dynamic_offsets);
         dispatch.dispatch();
+        Ok(())
     }
 }
 
@@ -122,6 +1239,8 @@ pub struct DispatchBuilder<'a, WorkgroupCountType, PushConstantsType> {
     pipeline: &'a Arc<Pipeline>,
     count: WorkgroupCountType,
     push_constants: PushConstantsType,
+    binding_sets: Vec<Arc<BindingSet>>,
+    dynamic_offsets: Vec<(String, usize)>,
 }
 
 
@@ -131,41 +1250,273 @@ impl<'a> DispatchBuilder<'a, (), ()> {
             pipeline: pipeline,
             count: (),
             push_constants: (),
+            binding_sets: vec![],
+            dynamic_offsets: vec![],
+        }
+    }
+}
+
+impl<'a, PushConstantsType> DispatchBuilder<'a, (), PushConstantsType> {
+    pub fn workgroup_count(self, x: usize, y: usize, z: usize) -> DispatchBuilder<'a, WorkgroupGrid, PushConstantsType> {
+        DispatchBuilder {
+            pipeline: self.pipeline,
+            count: WorkgroupGrid { x, y, z },
+            push_constants: self.push_constants,
+            binding_sets: self.binding_sets,
+            dynamic_offsets: self.dynamic_offsets,
+        }
+    }
+}
+
+impl<'a, PushConstantsType> DispatchBuilder<'a, WorkgroupGrid, PushConstantsType> {
+    // the first gl_WorkGroupID of the dispatch,
+    // the pipeline must be built with `PipelineBuilder::dispatch_base`
+    pub fn base(self, x: usize, y: usize, z: usize) -> DispatchBuilder<'a, WorkgroupRange, PushConstantsType> {
+        let range = WorkgroupRange {
+            base: WorkgroupGrid { x, y, z },
+            count: self.count,
+        };
+        DispatchBuilder {
+            pipeline: self.pipeline,
+            count: range,
+            push_constants: self.push_constants,
+            binding_sets: self.binding_sets,
+            dynamic_offsets: self.dynamic_offsets,
         }
     }
 }
 
 impl<'a, PushConstantsType> DispatchBuilder<'a, (), PushConstantsType> {
-    pub fn workgroup_count(self, x: usize, y: usize, z: usize) -> DispatchBuilder<'a, WorkgroupCount, PushConstantsType> {
+    // reads the workgroup count from the binding when the dispatch runs, 
+    // e.g. written by the previous dispatch. `offset` is in bytes within the binding region
+    pub fn indirect<VariantType>(self, binding: &BufferBindingView<VariantType>, offset: usize) 
+        -> DispatchBuilder<'a, IndirectRegion, PushConstantsType> {
+        let indirect = IndirectRegion {
+            buffer: Arc::clone(binding.buffer()),
+            region_index: binding.region_index(),
+            offset,
+        };
         DispatchBuilder {
             pipeline: self.pipeline,
-            count: WorkgroupCount { x: x as u32, y: y as u32, z: z as u32 },
+            count: indirect,
             push_constants: self.push_constants,
+            binding_sets: self.binding_sets,
+            dynamic_offsets: self.dynamic_offsets,
         }
     }
 }
 
 impl<'a, WorkgroupCountType> DispatchBuilder<'a, WorkgroupCountType, ()> {
-    pub fn push_constants(self, push_constants: Vec<ConstantEntry>) 
-        -> DispatchBuilder<'a, WorkgroupCountType, Vec<ConstantEntry>> {
+    // takes either `constants!` packed from offset zero or a `PushConstants`
+    pub fn push_constants(self, push_constants: impl Into<PushConstants>) 
+        -> DispatchBuilder<'a, WorkgroupCountType, PushConstants> {
         DispatchBuilder {
             pipeline: self.pipeline,
             count: self.count,
-            push_constants: push_constants,
+            push_constants: push_constants.into(),
+            binding_sets: self.binding_sets,
+            dynamic_offsets: self.dynamic_offsets,
+        }
+    }
+}
+
+impl<'a, WorkgroupCountType, PushConstantsType> DispatchBuilder<'a, WorkgroupCountType, PushConstantsType> {
+    // binds the set in place of the pipeline's own of the same set number, see `Pipeline::binding_set`.
+    // the other sets stay bound to the pipeline's
+    pub fn bind_set(mut self, binding_set: &Arc<BindingSet>) -> Self {
+        self.binding_sets.retain(|v| v.set() != binding_set.set());
+        self.binding_sets.push(Arc::clone(binding_set));
+        self
+    }
+
+    // the byte offset of the window of the dynamic binding within its region, zero by default.
+    // a multiple of minStorageBufferOffsetAlignment, see `PipelineBuilder::dynamic_binding`
+    pub fn dynamic_offset(mut self, name: impl Into<String>, offset: usize) -> Self {
+        let name = name.into();
+        self.dynamic_offsets.retain(|(v, _)| *v != name);
+        self.dynamic_offsets.push((name, offset));
+        self
+    }
+
+    // the sets must be laid out as the pipeline's, which may have been reloaded since they were made.
+    // indirect workgroup counts are read from the buffer of the pipeline
+    fn validate_binding_sets(binding_sets: &[Arc<BindingSet>], shader: &PipelineShader, command: &DispatchCommand) -> Result<()> {
+        for binding_set in binding_sets.iter() {
+            let set = binding_set.set();
+            let matches = shader.binding_set(set)
+                .map(|v| v.layout().layout() == binding_set.layout().layout())
+                .unwrap_or(false);
+            if !matches {
+                return Err(ErrorCode::BindingSetLayoutMismatch { set }.into())
+            }
+            if let DispatchCommand::Indirect(_) = command {
+                let is_default_buffer = shader.binding_set(set)
+                    .map(|v| Arc::ptr_eq(v.staging_buffer(), binding_set.staging_buffer()))
+                    .unwrap_or(false);
+                if set == 0 && !is_default_buffer {
+                    return Err(ErrorCode::BindingBufferMismatch.into())
+                }
+            }
         }
+        Ok(())
     }
 }
 
-impl<'a> DispatchBuilder<'a, WorkgroupCount, Vec<ConstantEntry>> {
+impl<'a, WorkgroupCountType: DispatchSize> DispatchBuilder<'a, WorkgroupCountType, PushConstants> {
+    // the dispatch keeps running the pipeline as of build even if it gets reloaded
     pub fn build(self) -> Result<Arc<CommandDispatch>> {
-        let compute_pipeline = &self.pipeline.compute_pipeline;
-        Ok(CommandDispatch::new(compute_pipeline, self.count, self.push_constants))
+        self.pipeline.reload_before_dispatch();
+        let command = self.count.command(self.pipeline)?;
+        let current = self.pipeline.current();
+        let compute_pipeline = &current.compute_pipeline;
+        let push_constants = self.push_constants.resolve(&current.interface, compute_pipeline.push_constant_size())?;
+        Self::validate_binding_sets(&self.binding_sets, &current, &command)?;
+        let dynamic_offsets = self.pipeline.dynamic_offsets(&current, &self.binding_sets, &self.dynamic_offsets)?;
+        Ok(CommandDispatch::new(compute_pipeline, command, push_constants, current.binding_sets(&self.binding_sets), &dynamic_offsets))
     }
 }
 
-impl<'a> DispatchBuilder<'a, WorkgroupCount, ()> {
+impl<'a, WorkgroupCountType: DispatchSize> DispatchBuilder<'a, WorkgroupCountType, ()> {
     pub fn build(self) -> Result<Arc<CommandDispatch>> {
-        let compute_pipeline = &self.pipeline.compute_pipeline;
-        Ok(CommandDispatch::new(compute_pipeline, self.count, vec![]))
+        self.pipeline.reload_before_dispatch();
+        let command = self.count.command(self.pipeline)?;
+        let current = self.pipeline.current();
+        Self::validate_binding_sets(&self.binding_sets, &current, &command)?;
+        let dynamic_offsets = self.pipeline.dynamic_offsets(&current, &self.binding_sets, &self.dynamic_offsets)?;
+        Ok(CommandDispatch::new(&current.compute_pipeline, command, vec![], current.binding_sets(&self.binding_sets), &dynamic_offsets))
+    }
+}
+
+// how many workgroups a dispatch runs, given either by the host or by a buffer region
+pub trait DispatchSize {
+    fn command(self, pipeline: &Pipeline) -> Result<DispatchCommand>;
+}
+
+// the number of workgroups given to `DispatchBuilder::workgroup_count`
+#[derive(Copy, Clone)]
+pub struct WorkgroupGrid {
+    x: usize,
+    y: usize,
+    z: usize,
+}
+
+impl DispatchSize for WorkgroupGrid {
+    fn command(self, pipeline: &Pipeline) -> Result<DispatchCommand> {
+        let count = WorkgroupCount::new(self.x, self.y, self.z)?
+            .check(pipeline.workgroup_count_limit())?;
+        Ok(count.into())
+    }
+}
+
+// workgroups from the base up to the count
+pub struct WorkgroupRange {
+    base: WorkgroupGrid,
+    count: WorkgroupGrid,
+}
+
+impl DispatchSize for WorkgroupRange {
+    fn command(self, pipeline: &Pipeline) -> Result<DispatchCommand> {
+        if !pipeline.options.dispatch_base {
+            return Err(ErrorCode::DispatchBaseNotEnabled.into())
+        }
+        // the base counts toward the limit
+        let limit = pipeline.workgroup_count_limit();
+        let base = [self.base.x, self.base.y, self.base.z];
+        let count = [self.count.x, self.count.y, self.count.z];
+        for axis in 0..3 {
+            let end = base[axis].saturating_add(count[axis]);
+            if end > limit[axis] as usize {
+                return Err(ErrorCode::WorkgroupCountExceedsLimit { axis, count: end, limit: limit[axis] }.into())
+            }
+        }
+        let base = WorkgroupCount::new(self.base.x, self.base.y, self.base.z)?;
+        let count = WorkgroupCount::new(self.count.x, self.count.y, self.count.z)?;
+        Ok(DispatchCommand::Base(base, count))
+    }
+}
+
+// a part of a grid of workgroups, see `Tile::split`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tile {
+    pub base: (usize, usize, usize),
+    pub count: (usize, usize, usize),
+}
+
+impl Tile {
+    // splits the grid of workgroups into tiles of the size at most, in the order of x, y and z
+    pub fn split(count: (usize, usize, usize), tile_size: (usize, usize, usize)) -> Vec<Tile> {
+        let ranges = |count: usize, size: usize| {
+            let size = size.max(1);
+            (0..count).step_by(size)
+                .map(move |base| (base, size.min(count - base)))
+        };
+        let mut tiles = vec![];
+        for (base_z, count_z) in ranges(count.2, tile_size.2) {
+            for (base_y, count_y) in ranges(count.1, tile_size.1) {
+                for (base_x, count_x) in ranges(count.0, tile_size.0) {
+                    let tile = Tile {
+                        base: (base_x, base_y, base_z),
+                        count: (count_x, count_y, count_z),
+                    };
+                    tiles.push(tile);
+                }
+            }
+        }
+        tiles
+    }
+}
+
+// a binding region holding `DispatchIndirectCommand` at the offset
+pub struct IndirectRegion {
+    buffer: Arc<Buffer>,
+    region_index: usize,
+    offset: usize,
+}
+
+impl DispatchSize for IndirectRegion {
+    fn command(self, pipeline: &Pipeline) -> Result<DispatchCommand> {
+        if !Arc::ptr_eq(&self.buffer, pipeline.buffer()) {
+            return Err(ErrorCode::BindingBufferMismatch.into())
+        }
+        let indirect = DispatchIndirect::new(self.buffer.staging_buffer(), self.region_index, self.offset)?;
+        Ok(indirect.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles(count: (usize, usize, usize), tile_size: (usize, usize, usize)) -> Vec<((usize, usize, usize), (usize, usize, usize))> {
+        Tile::split(count, tile_size).into_iter()
+            .map(|v| (v.base, v.count))
+            .collect()
+    }
+
+    #[test]
+    fn split_tiles() {
+        assert_eq!(tiles((10, 5, 1), (4, 4, 1)), [
+            ((0, 0, 0), (4, 4, 1)), ((4, 0, 0), (4, 4, 1)), ((8, 0, 0), (2, 4, 1)),
+            ((0, 4, 0), (4, 1, 1)), ((4, 4, 0), (4, 1, 1)), ((8, 4, 0), (2, 1, 1)),
+        ]);
+        assert_eq!(tiles((4, 4, 2), (8, 8, 1)), [((0, 0, 0), (4, 4, 1)), ((0, 0, 1), (4, 4, 1))]);
+    }
+
+    #[test]
+    fn split_tiles_cover_grid() {
+        let count = (13, 7, 3);
+        let tiles = Tile::split(count, (5, 3, 2));
+        assert_eq!(tiles.len(), 3 * 3 * 2);
+        let covered: usize = tiles.iter()
+            .map(|v| v.count.0 * v.count.1 * v.count.2)
+            .sum();
+        assert_eq!(covered, count.0 * count.1 * count.2);
+    }
+
+    #[test]
+    fn split_tiles_degenerate() {
+        // a zero tile size is taken as one
+        assert_eq!(tiles((2, 1, 1), (0, 0, 0)), [((0, 0, 0), (1, 1, 1)), ((1, 0, 0), (1, 1, 1))]);
+        assert!(Tile::split((0, 4, 4), (2, 2, 2)).is_empty());
     }
 }
@@ -0,0 +1,356 @@
+use crate::vk::*;
+use crate::error::Result;
+use crate::error::ErrorCode;
+use super::device::{Device};
+
+use std::ptr;
+use std::fmt;
+use std::cmp::Reverse;
+use std::mem::MaybeUninit;
+use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use libc::{c_void};
+
+// the number of the latest operations kept, see `Context::profile_records`
+const RECORD_CAPACITY: usize = 1024;
+
+// GPU durations of dispatches and transfers measured by timestamp queries
+pub struct Profiler {
+    device: Arc<Device>,
+    // nanoseconds per timestamp tick
+    period: f64,
+    // timestamps wrap around beyond the valid bits
+    valid_mask: u64,
+    state: Mutex<ProfilerState>,
+}
+
+#[derive(Default)]
+struct ProfilerState {
+    records: VecDeque<ProfileRecord>,
+    // in the order the pipelines were dispatched first
+    pipelines: Vec<PipelineProfile>,
+    host_to_device: OperationProfile,
+    device_to_host: OperationProfile,
+}
+
+impl Profiler {
+    pub fn new(device: &Arc<Device>) -> Result<Arc<Self>> {
+        let valid_bits = device.queue().family().timestamp_valid_bits();
+        if valid_bits == 0 {
+            return Err(ErrorCode::TimestampUnsupported.into())
+        }
+        let valid_mask = if valid_bits >= 64 {
+            u64::MAX
+        } else {
+            (1u64 << valid_bits) - 1
+        };
+        let period = device.physical_device().properties().limits.timestampPeriod as f64;
+        let profiler = Profiler {
+            device: Arc::clone(device),
+            period,
+            valid_mask,
+            state: Mutex::new(ProfilerState::default()),
+        };
+        Ok(Arc::new(profiler))
+    }
+
+    fn duration(&self, begin: u64, end: u64) -> Duration {
+        let ticks = end.wrapping_sub(begin) & self.valid_mask;
+        Duration::from_nanos((ticks as f64 * self.period) as u64)
+    }
+
+    fn record(&self, operation: &ProfiledOperation, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        match operation {
+            ProfiledOperation::Dispatch { pipeline, entry_point } => {
+                let index = state.pipelines.iter()
+                    .position(|v| v.pipeline == *pipeline);
+                let index = match index {
+                    Some(index) => index,
+                    None => {
+                        state.pipelines.push(PipelineProfile {
+                            pipeline: *pipeline,
+                            entry_point: entry_point.clone(),
+                            dispatches: OperationProfile::default(),
+                        });
+                        state.pipelines.len() - 1
+                    },
+                };
+                state.pipelines[index].dispatches.add(duration);
+            },
+            ProfiledOperation::HostToDevice { .. } => state.host_to_device.add(duration),
+            ProfiledOperation::DeviceToHost { .. } => state.device_to_host.add(duration),
+        }
+        if state.records.len() == RECORD_CAPACITY {
+            state.records.pop_front();
+        }
+        state.records.push_back(ProfileRecord { operation: operation.clone(), duration });
+    }
+
+    // the latest operations measured, the oldest first
+    pub fn records(&self) -> Vec<ProfileRecord> {
+        let state = self.state.lock().unwrap();
+        state.records.iter().cloned().collect()
+    }
+
+    // the pipelines taking the most GPU time first
+    pub fn report(&self) -> ProfileReport {
+        let state = self.state.lock().unwrap();
+        let mut pipelines = state.pipelines.clone();
+        pipelines.sort_by_key(|v| Reverse(v.dispatches.total));
+        ProfileReport {
+            pipelines,
+            host_to_device: state.host_to_device.clone(),
+            device_to_host: state.device_to_host.clone(),
+        }
+    }
+
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        *state = ProfilerState::default();
+    }
+}
+
+// a pair of timestamps written around the commands of a command buffer
+pub(crate) struct TimestampQuery {
+    handle: VkQueryPool,
+    profiler: Arc<Profiler>,
+    operation: ProfiledOperation,
+    state: Mutex<TimestampQueryState>,
+}
+
+#[derive(Default)]
+struct TimestampQueryState {
+    // whether a submission wrote timestamps not read yet
+    pending: bool,
+    last: Option<Duration>,
+}
+
+impl TimestampQuery {
+    pub fn new(profiler: &Arc<Profiler>, operation: ProfiledOperation) -> Self {
+        let device = &profiler.device;
+        unsafe {
+            let mut handle = MaybeUninit::<VkQueryPool>::zeroed();
+            let create_info = VkQueryPoolCreateInfo::new(VkQueryType::VK_QUERY_TYPE_TIMESTAMP, 2);
+            vkCreateQueryPool(device.handle(), &create_info, ptr::null(), handle.as_mut_ptr())
+                .into_result()
+                .unwrap();
+            TimestampQuery {
+                handle: handle.assume_init(),
+                profiler: Arc::clone(profiler),
+                operation,
+                state: Mutex::new(TimestampQueryState::default()),
+            }
+        }
+    }
+
+    // the queries are reset by the command buffer itself so that it can be submitted again
+    pub unsafe fn record_begin(&self, command_buffer: VkCommandBuffer) {
+        vkCmdResetQueryPool(command_buffer, self.handle, 0, 2);
+        vkCmdWriteTimestamp(command_buffer, VkPipelineStageFlagBits::VK_PIPELINE_STAGE_TOP_OF_PIPE_BIT, self.handle, 0);
+    }
+
+    pub unsafe fn record_end(&self, command_buffer: VkCommandBuffer) {
+        vkCmdWriteTimestamp(command_buffer, VkPipelineStageFlagBits::VK_PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT, self.handle, 1);
+    }
+
+    // the command buffer was submitted successfully
+    pub fn submitted(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.pending = true;
+    }
+
+    // reads the timestamps of the last submission into the profiler, waiting for them if needed
+    pub fn resolve(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.pending {
+            return
+        }
+        let mut timestamps = [0u64; 2];
+        unsafe {
+            vkGetQueryPoolResults(
+                self.profiler.device.handle(),
+                self.handle,
+                0,
+                2,
+                std::mem::size_of_val(&timestamps),
+                timestamps.as_mut_ptr() as *mut c_void,
+                std::mem::size_of::<u64>() as VkDeviceSize,
+                VkQueryResultFlagBits::VK_QUERY_RESULT_64_BIT as VkFlags |
+                    VkQueryResultFlagBits::VK_QUERY_RESULT_WAIT_BIT as VkFlags)
+                .into_result()
+                .unwrap();
+        }
+        let duration = self.profiler.duration(timestamps[0], timestamps[1]);
+        state.pending = false;
+        state.last = Some(duration);
+        self.profiler.record(&self.operation, duration);
+    }
+
+    // the GPU duration of the last submission read
+    pub fn last(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state.last
+    }
+}
+
+// the query pool is only used under `state` or by the command buffers recorded once
+unsafe impl Send for TimestampQuery {}
+unsafe impl Sync for TimestampQuery {}
+
+impl Drop for TimestampQuery {
+    fn drop(&mut self) {
+        log_debug!("Drop TimestampQuery");
+        unsafe {
+            vkDestroyQueryPool(self.profiler.device.handle(), self.handle, ptr::null());
+            self.handle = ptr::null_mut();
+        }
+    }
+}
+
+// what a GPU duration was measured for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfiledOperation {
+    // `pipeline` identifies the compiled pipeline, the same for the pipelines sharing it
+    Dispatch { pipeline: u64, entry_point: String },
+    HostToDevice { size: usize },
+    DeviceToHost { size: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileRecord {
+    operation: ProfiledOperation,
+    duration: Duration,
+}
+
+impl ProfileRecord {
+    #[inline]
+    pub fn operation(&self) -> &ProfiledOperation {
+        &self.operation
+    }
+
+    // between the timestamps written before and after the commands
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+// the GPU durations of operations of a kind
+#[derive(Debug, Clone, Default)]
+pub struct OperationProfile {
+    count: u64,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl OperationProfile {
+    fn add(&mut self, duration: Duration) {
+        if self.count == 0 {
+            self.min = duration;
+            self.max = duration;
+        } else {
+            self.min = self.min.min(duration);
+            self.max = self.max.max(duration);
+        }
+        self.count += 1;
+        self.total += duration;
+    }
+
+    #[inline]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    #[inline]
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    #[inline]
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    #[inline]
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::default()
+        }
+        Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+    }
+}
+
+impl fmt::Display for OperationProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} times, {:?} total, {:?} mean, {:?} min, {:?} max",
+            self.count, self.total, self.mean(), self.min, self.max)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PipelineProfile {
+    pipeline: u64,
+    entry_point: String,
+    dispatches: OperationProfile,
+}
+
+impl PipelineProfile {
+    // see `ProfiledOperation::Dispatch`
+    #[inline]
+    pub fn pipeline(&self) -> u64 {
+        self.pipeline
+    }
+
+    #[inline]
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    #[inline]
+    pub fn dispatches(&self) -> &OperationProfile {
+        &self.dispatches
+    }
+}
+
+// the GPU time spent on each pipeline and on the transfers, see `Context::profile_report`
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    pipelines: Vec<PipelineProfile>,
+    host_to_device: OperationProfile,
+    device_to_host: OperationProfile,
+}
+
+impl ProfileReport {
+    // the pipelines taking the most GPU time first
+    #[inline]
+    pub fn pipelines(&self) -> &[PipelineProfile] {
+        &self.pipelines
+    }
+
+    #[inline]
+    pub fn host_to_device(&self) -> &OperationProfile {
+        &self.host_to_device
+    }
+
+    #[inline]
+    pub fn device_to_host(&self) -> &OperationProfile {
+        &self.device_to_host
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for profile in self.pipelines.iter() {
+            writeln!(f, "pipeline {} ({}): {}", profile.pipeline, profile.entry_point, profile.dispatches)?;
+        }
+        writeln!(f, "host to device: {}", self.host_to_device)?;
+        write!(f, "device to host: {}", self.device_to_host)
+    }
+}
@@ -0,0 +1,1333 @@
+
+use crate::error::Result;
+use crate::error::ErrorCode;
+use crate::dispatch::{DescriptorArray, DynamicBinding};
+
+use std::collections::{HashMap, HashSet};
+
+// @see https://www.khronos.org/registry/spir-v/specs/unified1/SPIRV.html
+
+const SPIRV_MAGIC_NUMBER: u32 = 0x07230203;
+const SPIRV_HEADER_WORD_COUNT: usize = 5;
+const SPIRV_HEADER_VERSION: usize = 1;
+const SPIRV_HEADER_BOUND: usize = 3;
+
+// opcodes
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_CAPABILITY: u32 = 17;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT_TRUE: u32 = 41;
+const OP_CONSTANT_FALSE: u32 = 42;
+const OP_CONSTANT: u32 = 43;
+const OP_CONSTANT_COMPOSITE: u32 = 44;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_FUNCTION: u32 = 54;
+const OP_FUNCTION_END: u32 = 56;
+const OP_FUNCTION_CALL: u32 = 57;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+// decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+
+const EXECUTION_MODEL_GL_COMPUTE: u32 = 5;
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const EXECUTION_MODE_LOCAL_SIZE_ID: u32 = 38;
+
+const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+const DIM_BUFFER: u32 = 5;
+
+// capabilities
+pub(crate) const CAPABILITY_MATRIX: u32 = 0;
+pub(crate) const CAPABILITY_SHADER: u32 = 1;
+pub(crate) const CAPABILITY_FLOAT16: u32 = 9;
+pub(crate) const CAPABILITY_FLOAT64: u32 = 10;
+pub(crate) const CAPABILITY_INT64: u32 = 11;
+pub(crate) const CAPABILITY_INT64_ATOMICS: u32 = 12;
+pub(crate) const CAPABILITY_INT16: u32 = 22;
+pub(crate) const CAPABILITY_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING: u32 = 30;
+pub(crate) const CAPABILITY_INT8: u32 = 39;
+pub(crate) const CAPABILITY_STORAGE_IMAGE_EXTENDED_FORMATS: u32 = 49;
+pub(crate) const CAPABILITY_STORAGE_IMAGE_READ_WITHOUT_FORMAT: u32 = 55;
+pub(crate) const CAPABILITY_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT: u32 = 56;
+pub(crate) const CAPABILITY_GROUP_NON_UNIFORM: u32 = 61;
+pub(crate) const CAPABILITY_GROUP_NON_UNIFORM_QUAD: u32 = 68;
+pub(crate) const CAPABILITY_STORAGE_BUFFER_16BIT_ACCESS: u32 = 4433;
+pub(crate) const CAPABILITY_UNIFORM_AND_STORAGE_BUFFER_16BIT_ACCESS: u32 = 4434;
+pub(crate) const CAPABILITY_STORAGE_PUSH_CONSTANT16: u32 = 4435;
+pub(crate) const CAPABILITY_VARIABLE_POINTERS_STORAGE_BUFFER: u32 = 4441;
+pub(crate) const CAPABILITY_VARIABLE_POINTERS: u32 = 4442;
+pub(crate) const CAPABILITY_STORAGE_BUFFER_8BIT_ACCESS: u32 = 4448;
+pub(crate) const CAPABILITY_UNIFORM_AND_STORAGE_BUFFER_8BIT_ACCESS: u32 = 4449;
+pub(crate) const CAPABILITY_STORAGE_PUSH_CONSTANT8: u32 = 4450;
+pub(crate) const CAPABILITY_SHADER_NON_UNIFORM: u32 = 5301;
+pub(crate) const CAPABILITY_RUNTIME_DESCRIPTOR_ARRAY: u32 = 5302;
+pub(crate) const CAPABILITY_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING: u32 = 5308;
+pub(crate) const CAPABILITY_VULKAN_MEMORY_MODEL: u32 = 5345;
+pub(crate) const CAPABILITY_PHYSICAL_STORAGE_BUFFER_ADDRESSES: u32 = 5347;
+
+// the names of the capabilities compute shaders commonly declare
+pub fn capability_name(capability: u32) -> Option<&'static str> {
+    let name = match capability {
+        CAPABILITY_MATRIX => "Matrix",
+        CAPABILITY_SHADER => "Shader",
+        CAPABILITY_FLOAT16 => "Float16",
+        CAPABILITY_FLOAT64 => "Float64",
+        CAPABILITY_INT64 => "Int64",
+        CAPABILITY_INT64_ATOMICS => "Int64Atomics",
+        CAPABILITY_INT16 => "Int16",
+        CAPABILITY_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING => "StorageBufferArrayDynamicIndexing",
+        CAPABILITY_INT8 => "Int8",
+        CAPABILITY_STORAGE_IMAGE_EXTENDED_FORMATS => "StorageImageExtendedFormats",
+        CAPABILITY_STORAGE_IMAGE_READ_WITHOUT_FORMAT => "StorageImageReadWithoutFormat",
+        CAPABILITY_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT => "StorageImageWriteWithoutFormat",
+        CAPABILITY_GROUP_NON_UNIFORM => "GroupNonUniform",
+        62 => "GroupNonUniformVote",
+        63 => "GroupNonUniformArithmetic",
+        64 => "GroupNonUniformBallot",
+        65 => "GroupNonUniformShuffle",
+        66 => "GroupNonUniformShuffleRelative",
+        67 => "GroupNonUniformClustered",
+        CAPABILITY_GROUP_NON_UNIFORM_QUAD => "GroupNonUniformQuad",
+        CAPABILITY_STORAGE_BUFFER_16BIT_ACCESS => "StorageBuffer16BitAccess",
+        CAPABILITY_UNIFORM_AND_STORAGE_BUFFER_16BIT_ACCESS => "UniformAndStorageBuffer16BitAccess",
+        CAPABILITY_STORAGE_PUSH_CONSTANT16 => "StoragePushConstant16",
+        CAPABILITY_VARIABLE_POINTERS_STORAGE_BUFFER => "VariablePointersStorageBuffer",
+        CAPABILITY_VARIABLE_POINTERS => "VariablePointers",
+        CAPABILITY_STORAGE_BUFFER_8BIT_ACCESS => "StorageBuffer8BitAccess",
+        CAPABILITY_UNIFORM_AND_STORAGE_BUFFER_8BIT_ACCESS => "UniformAndStorageBuffer8BitAccess",
+        CAPABILITY_STORAGE_PUSH_CONSTANT8 => "StoragePushConstant8",
+        CAPABILITY_SHADER_NON_UNIFORM => "ShaderNonUniform",
+        CAPABILITY_RUNTIME_DESCRIPTOR_ARRAY => "RuntimeDescriptorArray",
+        CAPABILITY_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING => "StorageBufferArrayNonUniformIndexing",
+        CAPABILITY_VULKAN_MEMORY_MODEL => "VulkanMemoryModel",
+        CAPABILITY_PHYSICAL_STORAGE_BUFFER_ADDRESSES => "PhysicalStorageBufferAddresses",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExecutionModel {
+    GLCompute,
+    Other(u32),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DescriptorType {
+    StorageBuffer,
+    UniformBuffer,
+    StorageImage,
+    SampledImage,
+    CombinedImageSampler,
+    Sampler,
+    UniformTexelBuffer,
+    StorageTexelBuffer,
+}
+
+// how many descriptors a binding declares
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DescriptorCount {
+    Single,
+    Array(u32),
+    // e.g. `buffer Data { ... } inputs[]`
+    Runtime,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScalarType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+}
+
+impl ScalarType {
+    // size in bytes as laid out in specialization data
+    // booleans are passed as VkBool32
+    pub fn size(&self) -> usize {
+        match *self {
+            ScalarType::Bool => 4,
+            ScalarType::Int { width, .. } => width as usize / 8,
+            ScalarType::Float { width } => width as usize / 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryPoint {
+    name: String,
+    execution_model: ExecutionModel,
+    local_size: [u32; 3],
+    local_size_ids: [Option<u32>; 3],
+    // the global variables statically used by the entry point, sorted
+    variables: Vec<u32>,
+}
+
+impl EntryPoint {
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn execution_model(&self) -> ExecutionModel {
+        self.execution_model
+    }
+
+    // the default workgroup size declared by the shader
+    #[inline]
+    pub fn local_size(&self) -> [u32; 3] {
+        self.local_size
+    }
+
+    // specialization constant IDs overriding each workgroup dimension, if any
+    #[inline]
+    pub fn local_size_ids(&self) -> [Option<u32>; 3] {
+        self.local_size_ids
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockMember {
+    name: Option<String>,
+    offset: usize,
+    size: usize,
+    scalar_type: Option<ScalarType>,
+}
+
+impl BlockMember {
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // zero for a runtime array member
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // none unless the member is a scalar
+    #[inline]
+    pub fn scalar_type(&self) -> Option<ScalarType> {
+        self.scalar_type
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockLayout {
+    name: Option<String>,
+    size: usize,
+    runtime_array_stride: Option<usize>,
+    members: Vec<BlockMember>,
+}
+
+impl BlockLayout {
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // the size of the block excluding its trailing runtime array
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    pub fn runtime_array_stride(&self) -> Option<usize> {
+        self.runtime_array_stride
+    }
+
+    #[inline]
+    pub fn members(&self) -> &[BlockMember] {
+        &self.members
+    }
+
+    // whether a buffer of `size` bytes satisfies this block
+    fn accepts(&self, size: usize) -> bool {
+        if size < self.size {
+            return false
+        }
+        match self.runtime_array_stride {
+            Some(stride) if stride > 0 => (size - self.size).is_multiple_of(stride),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DescriptorBinding {
+    variable: u32,
+    set: u32,
+    binding: u32,
+    descriptor_type: DescriptorType,
+    descriptor_count: DescriptorCount,
+    name: Option<String>,
+    block: Option<BlockLayout>,
+}
+
+impl DescriptorBinding {
+    #[inline]
+    pub fn set(&self) -> u32 {
+        self.set
+    }
+
+    #[inline]
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    #[inline]
+    pub fn descriptor_type(&self) -> DescriptorType {
+        self.descriptor_type
+    }
+
+    #[inline]
+    pub fn descriptor_count(&self) -> DescriptorCount {
+        self.descriptor_count
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // the memory layout of buffer bindings
+    #[inline]
+    pub fn block(&self) -> Option<&BlockLayout> {
+        self.block.as_ref()
+    }
+
+    // whether a buffer region of the size fits the block
+    pub(crate) fn validate_region_size(&self, size: usize) -> Result<()> {
+        if let Some(block) = self.block.as_ref() {
+            if !block.accepts(size) {
+                let (set, binding) = (self.set, self.binding);
+                let expected = block.size;
+                let stride = block.runtime_array_stride;
+                return Err(ErrorCode::BindingSizeMismatch { set, binding, expected, stride, actual: size }.into())
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpecializationConstant {
+    id: u32,
+    name: Option<String>,
+    scalar_type: ScalarType,
+}
+
+impl SpecializationConstant {
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    pub fn scalar_type(&self) -> ScalarType {
+        self.scalar_type
+    }
+}
+
+// The resources a SPIR-V module declares, obtained by parsing the module
+// so that what the application provides can be checked against it
+#[derive(Debug, Clone)]
+pub struct ShaderInterface {
+    entry_points: Vec<EntryPoint>,
+    bindings: Vec<DescriptorBinding>,
+    // a module with several entry points may declare a push constant block for each
+    push_constants: Vec<(u32, BlockLayout)>,
+    specialization_constants: Vec<SpecializationConstant>,
+    version: (u32, u32),
+    capabilities: Vec<u32>,
+}
+
+impl ShaderInterface {
+    pub fn parse(words: &[u32]) -> Result<Self> {
+        let module = SpirvModule::parse(words)?;
+        Ok(module.interface())
+    }
+
+    #[inline]
+    pub fn entry_points(&self) -> &[EntryPoint] {
+        &self.entry_points
+    }
+
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPoint> {
+        self.entry_points.iter()
+            .find(|v| v.name == name)
+    }
+
+    #[inline]
+    pub fn bindings(&self) -> &[DescriptorBinding] {
+        &self.bindings
+    }
+
+    pub fn binding(&self, set: u32, binding: u32) -> Option<&DescriptorBinding> {
+        self.bindings.iter()
+            .find(|v| v.set == set && v.binding == binding)
+    }
+
+    // looks up the instance name first, then the block name and lastly
+    // the members of the block, e.g. `values` in `buffer Block { uint values[]; };`
+    pub fn binding_by_name(&self, name: &str) -> Option<&DescriptorBinding> {
+        let name = Some(name);
+        self.bindings.iter()
+            .find(|v| v.name() == name)
+            .or_else(|| {
+                self.bindings.iter()
+                    .find(|v| v.block().and_then(|v| v.name()) == name)
+            })
+            .or_else(|| {
+                self.bindings.iter()
+                    .find(|v| {
+                        v.block()
+                            .map(|v| v.members().iter().any(|v| v.name() == name))
+                            .unwrap_or(false)
+                    })
+            })
+    }
+
+    #[inline]
+    pub fn push_constants(&self) -> Option<&BlockLayout> {
+        self.push_constants.first().map(|(_, v)| v)
+    }
+
+    #[inline]
+    pub fn specialization_constants(&self) -> &[SpecializationConstant] {
+        &self.specialization_constants
+    }
+
+    pub fn specialization_constant(&self, id: u32) -> Option<&SpecializationConstant> {
+        self.specialization_constants.iter()
+            .find(|v| v.id == id)
+    }
+
+    pub fn specialization_constant_by_name(&self, name: &str) -> Option<&SpecializationConstant> {
+        self.specialization_constants.iter()
+            .find(|v| v.name() == Some(name))
+    }
+
+    // the resources the named entry point uses, 
+    // i.e. the bindings and push constants of other entry points are left out
+    pub fn entry_point_interface(&self, name: &str) -> Option<ShaderInterface> {
+        let entry_point = self.entry_point(name)?;
+        let uses = |variable: u32| entry_point.variables.binary_search(&variable).is_ok();
+        let interface = ShaderInterface {
+            entry_points: vec![entry_point.clone()],
+            bindings: self.bindings.iter()
+                .filter(|v| uses(v.variable))
+                .cloned()
+                .collect(),
+            push_constants: self.push_constants.iter()
+                .filter(|(variable, _)| uses(*variable))
+                .cloned()
+                .collect(),
+            specialization_constants: self.specialization_constants.clone(),
+            version: self.version,
+            capabilities: self.capabilities.clone(),
+        };
+        Some(interface)
+    }
+
+    // the SPIR-V version the module is written in as (major, minor)
+    #[inline]
+    pub fn version(&self) -> (u32, u32) {
+        self.version
+    }
+
+    // the capabilities the module declares with OpCapability
+    #[inline]
+    pub fn capabilities(&self) -> &[u32] {
+        &self.capabilities
+    }
+
+    pub(crate) fn validate_entry_point(&self, name: &str) -> Result<ShaderInterface> {
+        self.entry_point(name)
+            .filter(|v| v.execution_model == ExecutionModel::GLCompute)
+            .and_then(|v| self.entry_point_interface(v.name()))
+            .ok_or_else(|| ErrorCode::ShaderEntryPointNotFound(name.to_string()).into())
+    }
+
+    // buffer regions are bound to the bindings of the set in order
+    // the regions bound to descriptor arrays are checked as they are bound,
+    // the windows of dynamic bindings are checked instead of their regions
+    pub(crate) fn validate_bindings(&self, 
+        set: u32, 
+        region_sizes: &[usize], 
+        descriptor_arrays: &[DescriptorArray], 
+        dynamic_bindings: &[DynamicBinding]) -> Result<()> {
+        for binding in self.bindings.iter().filter(|v| v.set == set) {
+            let index = binding.binding;
+            let array = descriptor_arrays.iter()
+                .find(|v| v.binding == index);
+            match (binding.descriptor_count, array) {
+                (DescriptorCount::Single, Some(_)) => 
+                    return Err(ErrorCode::DescriptorArrayBindingSingle { set, binding: index }.into()),
+                (DescriptorCount::Single, None) => (),
+                (_, None) => 
+                    return Err(ErrorCode::DescriptorArrayBindingUndeclared { set, binding: index }.into()),
+                (DescriptorCount::Array(length), Some(array)) if length > array.max_count => {
+                    let max_count = array.max_count;
+                    return Err(ErrorCode::DescriptorArrayLengthExceedsCount { set, binding: index, length, max_count }.into())
+                },
+                (_, Some(_)) => (),
+            }
+            let size = match array {
+                Some(_) => None,
+                None => {
+                    let size = *region_sizes.get(index as usize)
+                        .ok_or(ErrorCode::BindingNotFound { set, binding: index })?;
+                    Some(size)
+                },
+            };
+            if binding.descriptor_type != DescriptorType::StorageBuffer {
+                let descriptor_type = binding.descriptor_type;
+                return Err(ErrorCode::BindingTypeMismatch { set, binding: index, descriptor_type }.into())
+            }
+            let dynamic = dynamic_bindings.iter()
+                .find(|v| v.binding == index);
+            match (size, dynamic) {
+                (Some(size), Some(dynamic)) => {
+                    let range = dynamic.range;
+                    if range > size {
+                        return Err(ErrorCode::DynamicOffsetOutOfRange { set, binding: index, offset: 0, range, size }.into())
+                    }
+                    binding.validate_region_size(range)?;
+                },
+                (Some(size), None) => binding.validate_region_size(size)?,
+                (None, _) => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+enum SpirvType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+struct SpirvConstant {
+    type_id: u32,
+    value: u32,
+    is_specialization: bool,
+}
+
+struct SpirvVariable {
+    id: u32,
+    type_id: u32,
+    storage_class: u32,
+}
+
+struct SpirvEntryPoint {
+    function_id: u32,
+    name: String,
+    execution_model: u32,
+    local_size: Option<[u32; 3]>,
+    local_size_ids: Option<[u32; 3]>,
+}
+
+#[derive(Default)]
+struct SpirvDecorations {
+    spec_id: Option<u32>,
+    buffer_block: bool,
+    array_stride: Option<u32>,
+    built_in: Option<u32>,
+    binding: Option<u32>,
+    descriptor_set: Option<u32>,
+}
+
+#[derive(Default)]
+struct SpirvMemberDecorations {
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+}
+
+#[derive(Default)]
+struct SpirvModule {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    entry_points: Vec<SpirvEntryPoint>,
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, SpirvConstant>,
+    composites: HashMap<u32, Vec<u32>>,
+    variables: Vec<SpirvVariable>,
+    variable_ids: HashSet<u32>,
+    decorations: HashMap<u32, SpirvDecorations>,
+    member_decorations: HashMap<(u32, u32), SpirvMemberDecorations>,
+    // global variables and functions referenced from each function body
+    function_variables: HashMap<u32, HashSet<u32>>,
+    function_calls: HashMap<u32, Vec<u32>>,
+    version: (u32, u32),
+    capabilities: Vec<u32>,
+}
+
+impl SpirvModule {
+    fn parse(words: &[u32]) -> Result<Self> {
+        if words.len() < SPIRV_HEADER_WORD_COUNT {
+            return Err(ErrorCode::ShaderTruncated.into())
+        }
+        match words[0] {
+            SPIRV_MAGIC_NUMBER => (),
+            v if v == SPIRV_MAGIC_NUMBER.swap_bytes() => return Err(ErrorCode::ShaderEndiannessMismatch.into()),
+            v => return Err(ErrorCode::ShaderMagicNumberMismatch(v).into()),
+        }
+        let version = words[SPIRV_HEADER_VERSION];
+        let bound = words[SPIRV_HEADER_BOUND];
+        let mut module = SpirvModule {
+            version: ((version >> 16) & 0xff, (version >> 8) & 0xff),
+            ..SpirvModule::default()
+        };
+        let mut position = SPIRV_HEADER_WORD_COUNT;
+        let mut function = None;
+        while position < words.len() {
+            let word_count = (words[position] >> 16) as usize;
+            let opcode = words[position] & 0xffff;
+            if word_count == 0 {
+                return Err(ErrorCode::ShaderMalformed.into())
+            }
+            if position + word_count > words.len() {
+                return Err(ErrorCode::ShaderTruncated.into())
+            }
+            let operands = &words[position + 1 .. position + word_count];
+            // all the declarations precede function definitions
+            match (opcode, function) {
+                (OP_FUNCTION, _) => {
+                    let function_id = *operands.get(1)
+                        .ok_or(ErrorCode::ShaderMalformed)?;
+                    function = Some(function_id);
+                },
+                (OP_FUNCTION_END, _) => function = None,
+                (_, Some(function_id)) => module.parse_function_instruction(function_id, opcode, operands),
+                (_, None) => module.parse_instruction(opcode, operands)?,
+            }
+            position += word_count;
+        }
+        // a module cut at an instruction boundary still leaves a function open
+        if function.is_some() {
+            return Err(ErrorCode::ShaderTruncated.into())
+        }
+        module.validate_bound(bound)?;
+        let has_compute = module.entry_points.iter()
+            .any(|v| v.execution_model == EXECUTION_MODEL_GL_COMPUTE);
+        if !has_compute {
+            return Err(ErrorCode::ShaderComputeEntryPointNotFound.into())
+        }
+        Ok(module)
+    }
+
+    // every result id is less than the bound the header declares
+    fn validate_bound(&self, bound: u32) -> Result<()> {
+        let ids = self.types.keys()
+            .chain(self.constants.keys())
+            .chain(self.variables.iter().map(|v| &v.id))
+            .chain(self.entry_points.iter().map(|v| &v.function_id))
+            .chain(self.function_variables.keys());
+        match ids.max() {
+            Some(&id) if id >= bound => Err(ErrorCode::ShaderIdOutOfBound { id, bound }.into()),
+            _ => Ok(()),
+        }
+    }
+
+    // only records which global variables and functions are referenced
+    fn parse_function_instruction(&mut self, function_id: u32, opcode: u32, operands: &[u32]) {
+        if opcode == OP_FUNCTION_CALL {
+            if let Some(&callee) = operands.get(2) {
+                self.function_calls.entry(function_id)
+                    .or_default()
+                    .push(callee);
+            }
+        }
+        let variable_ids = &self.variable_ids;
+        let referenced = operands.iter()
+            .filter(|&id| variable_ids.contains(id));
+        self.function_variables.entry(function_id)
+            .or_default()
+            .extend(referenced);
+    }
+
+    // the global variables reachable from the function through calls
+    fn static_variables(&self, function_id: u32) -> Vec<u32> {
+        let mut visited = HashSet::<u32>::new();
+        let mut variables = HashSet::<u32>::new();
+        let mut functions = vec![function_id];
+        while let Some(function_id) = functions.pop() {
+            if !visited.insert(function_id) {
+                continue
+            }
+            if let Some(referenced) = self.function_variables.get(&function_id) {
+                variables.extend(referenced);
+            }
+            if let Some(callees) = self.function_calls.get(&function_id) {
+                functions.extend(callees);
+            }
+        }
+        let mut variables: Vec<u32> = variables.into_iter().collect();
+        variables.sort();
+        variables
+    }
+
+    fn parse_instruction(&mut self, opcode: u32, operands: &[u32]) -> Result<()> {
+        let operand = |index: usize| -> Result<u32> {
+            operands.get(index)
+                .cloned()
+                .ok_or_else(|| ErrorCode::ShaderMalformed.into())
+        };
+        match opcode {
+            OP_CAPABILITY => self.capabilities.push(operand(0)?),
+            // anonymous instances are given empty names
+            OP_NAME => {
+                let name = parse_string(operands.get(1..).unwrap_or(&[]));
+                if !name.is_empty() {
+                    self.names.insert(operand(0)?, name);
+                }
+            },
+            OP_MEMBER_NAME => {
+                let name = parse_string(operands.get(2..).unwrap_or(&[]));
+                if !name.is_empty() {
+                    self.member_names.insert((operand(0)?, operand(1)?), name);
+                }
+            },
+            OP_ENTRY_POINT => {
+                let entry_point = SpirvEntryPoint {
+                    execution_model: operand(0)?,
+                    function_id: operand(1)?,
+                    name: parse_string(operands.get(2..).unwrap_or(&[])),
+                    local_size: None,
+                    local_size_ids: None,
+                };
+                self.entry_points.push(entry_point);
+            },
+            OP_EXECUTION_MODE => {
+                let function_id = operand(0)?;
+                let mode = operand(1)?;
+                let size = [operand(2).unwrap_or(1), operand(3).unwrap_or(1), operand(4).unwrap_or(1)];
+                let entry_point = self.entry_points.iter_mut()
+                    .find(|v| v.function_id == function_id);
+                if let Some(entry_point) = entry_point {
+                    match mode {
+                        EXECUTION_MODE_LOCAL_SIZE => entry_point.local_size = Some(size),
+                        EXECUTION_MODE_LOCAL_SIZE_ID => entry_point.local_size_ids = Some(size),
+                        _ => (),
+                    }
+                }
+            },
+            OP_TYPE_BOOL => {
+                self.types.insert(operand(0)?, SpirvType::Bool);
+            },
+            OP_TYPE_INT => {
+                let ty = SpirvType::Int { width: operand(1)?, signed: operand(2)? != 0 };
+                self.types.insert(operand(0)?, ty);
+            },
+            OP_TYPE_FLOAT => {
+                self.types.insert(operand(0)?, SpirvType::Float { width: operand(1)? });
+            },
+            OP_TYPE_VECTOR => {
+                self.types.insert(operand(0)?, SpirvType::Vector { component: operand(1)?, count: operand(2)? });
+            },
+            OP_TYPE_MATRIX => {
+                self.types.insert(operand(0)?, SpirvType::Matrix { column: operand(1)?, count: operand(2)? });
+            },
+            OP_TYPE_IMAGE => {
+                self.types.insert(operand(0)?, SpirvType::Image { dim: operand(2)?, sampled: operand(6)? });
+            },
+            OP_TYPE_SAMPLER => {
+                self.types.insert(operand(0)?, SpirvType::Sampler);
+            },
+            OP_TYPE_SAMPLED_IMAGE => {
+                self.types.insert(operand(0)?, SpirvType::SampledImage);
+            },
+            OP_TYPE_ARRAY => {
+                self.types.insert(operand(0)?, SpirvType::Array { element: operand(1)?, length: operand(2)? });
+            },
+            OP_TYPE_RUNTIME_ARRAY => {
+                self.types.insert(operand(0)?, SpirvType::RuntimeArray { element: operand(1)? });
+            },
+            OP_TYPE_STRUCT => {
+                self.types.insert(operand(0)?, SpirvType::Struct { members: operands.get(1..).unwrap_or(&[]).to_vec() });
+            },
+            OP_TYPE_POINTER => {
+                self.types.insert(operand(0)?, SpirvType::Pointer { pointee: operand(2)? });
+            },
+            OP_CONSTANT | OP_SPEC_CONSTANT => {
+                let constant = SpirvConstant {
+                    type_id: operand(0)?,
+                    value: operand(2)?,
+                    is_specialization: opcode == OP_SPEC_CONSTANT,
+                };
+                self.constants.insert(operand(1)?, constant);
+            },
+            OP_CONSTANT_TRUE | OP_CONSTANT_FALSE | OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE => {
+                let constant = SpirvConstant {
+                    type_id: operand(0)?,
+                    value: (opcode == OP_CONSTANT_TRUE || opcode == OP_SPEC_CONSTANT_TRUE) as u32,
+                    is_specialization: opcode == OP_SPEC_CONSTANT_TRUE || opcode == OP_SPEC_CONSTANT_FALSE,
+                };
+                self.constants.insert(operand(1)?, constant);
+            },
+            OP_CONSTANT_COMPOSITE | OP_SPEC_CONSTANT_COMPOSITE => {
+                self.composites.insert(operand(1)?, operands.get(2..).unwrap_or(&[]).to_vec());
+            },
+            OP_VARIABLE => {
+                let variable = SpirvVariable {
+                    type_id: operand(0)?,
+                    id: operand(1)?,
+                    storage_class: operand(2)?,
+                };
+                self.variable_ids.insert(variable.id);
+                self.variables.push(variable);
+            },
+            OP_DECORATE => {
+                let decorations = self.decorations.entry(operand(0)?)
+                    .or_default();
+                match operand(1)? {
+                    DECORATION_SPEC_ID => decorations.spec_id = Some(operand(2)?),
+                    DECORATION_BUFFER_BLOCK => decorations.buffer_block = true,
+                    DECORATION_ARRAY_STRIDE => decorations.array_stride = Some(operand(2)?),
+                    DECORATION_BUILT_IN => decorations.built_in = Some(operand(2)?),
+                    DECORATION_BINDING => decorations.binding = Some(operand(2)?),
+                    DECORATION_DESCRIPTOR_SET => decorations.descriptor_set = Some(operand(2)?),
+                    _ => (),
+                }
+            },
+            OP_MEMBER_DECORATE => {
+                let decorations = self.member_decorations.entry((operand(0)?, operand(1)?))
+                    .or_default();
+                match operand(2)? {
+                    DECORATION_OFFSET => decorations.offset = Some(operand(3)?),
+                    DECORATION_MATRIX_STRIDE => decorations.matrix_stride = Some(operand(3)?),
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn interface(&self) -> ShaderInterface {
+        let entry_points = self.entry_points.iter()
+            .map(|v| self.entry_point(v))
+            .collect();
+        let mut bindings: Vec<DescriptorBinding> = self.variables.iter()
+            .filter_map(|v| self.descriptor_binding(v))
+            .collect();
+        bindings.sort_by_key(|v| (v.set, v.binding));
+        let push_constants = self.variables.iter()
+            .filter(|v| v.storage_class == STORAGE_CLASS_PUSH_CONSTANT)
+            .filter_map(|v| {
+                let layout = self.block_layout(self.pointee(v.type_id)?)?;
+                Some((v.id, layout))
+            })
+            .collect();
+        let mut specialization_constants: Vec<SpecializationConstant> = self.constants.iter()
+            .filter(|(_, v)| v.is_specialization)
+            .filter_map(|(&id, v)| {
+                let spec_id = self.decorations.get(&id)?.spec_id?;
+                let constant = SpecializationConstant {
+                    id: spec_id,
+                    name: self.names.get(&id).cloned(),
+                    scalar_type: self.scalar_type(v.type_id)?,
+                };
+                Some(constant)
+            })
+            .collect();
+        specialization_constants.sort_by_key(|v| v.id);
+        ShaderInterface {
+            entry_points,
+            bindings,
+            push_constants,
+            specialization_constants,
+            version: self.version,
+            capabilities: self.capabilities.clone(),
+        }
+    }
+
+    fn entry_point(&self, entry_point: &SpirvEntryPoint) -> EntryPoint {
+        let execution_model = match entry_point.execution_model {
+            EXECUTION_MODEL_GL_COMPUTE => ExecutionModel::GLCompute,
+            v => ExecutionModel::Other(v),
+        };
+        let mut local_size = entry_point.local_size.unwrap_or([1, 1, 1]);
+        let mut local_size_ids = [None, None, None];
+        if let Some(ids) = entry_point.local_size_ids {
+            for (index, id) in ids.iter().enumerate() {
+                if let Some(constant) = self.constants.get(id) {
+                    local_size[index] = constant.value;
+                }
+                local_size_ids[index] = self.decorations.get(id).and_then(|v| v.spec_id);
+            }
+        }
+        // the WorkgroupSize built-in decorated constant takes precedence over the execution modes
+        let workgroup_size = self.composites.iter()
+            .find(|(id, _)| {
+                self.decorations.get(id)
+                    .and_then(|v| v.built_in) == Some(BUILT_IN_WORKGROUP_SIZE)
+            });
+        if let Some((_, constituents)) = workgroup_size {
+            for (index, id) in constituents.iter().take(3).enumerate() {
+                if let Some(constant) = self.constants.get(id) {
+                    local_size[index] = constant.value;
+                }
+                local_size_ids[index] = self.decorations.get(id).and_then(|v| v.spec_id);
+            }
+        }
+        EntryPoint {
+            name: entry_point.name.clone(),
+            execution_model,
+            local_size,
+            local_size_ids,
+            variables: self.static_variables(entry_point.function_id),
+        }
+    }
+
+    fn descriptor_binding(&self, variable: &SpirvVariable) -> Option<DescriptorBinding> {
+        let decorations = self.decorations.get(&variable.id)?;
+        let binding = decorations.binding?;
+        let set = decorations.descriptor_set.unwrap_or(0);
+        let pointee = self.pointee(variable.type_id)?;
+        // descriptor arrays share the binding of their element type
+        let (element, descriptor_count) = match self.types.get(&pointee)? {
+            SpirvType::Array { element, length } => {
+                let length = self.constants.get(length)?.value;
+                (*element, DescriptorCount::Array(length))
+            },
+            SpirvType::RuntimeArray { element } => (*element, DescriptorCount::Runtime),
+            _ => (pointee, DescriptorCount::Single),
+        };
+        let element_type = self.types.get(&element)?;
+        let descriptor_type = match (variable.storage_class, element_type) {
+            (STORAGE_CLASS_STORAGE_BUFFER, _) => DescriptorType::StorageBuffer,
+            (STORAGE_CLASS_UNIFORM, _) => {
+                let is_buffer_block = self.decorations.get(&element)
+                    .map(|v| v.buffer_block)
+                    .unwrap_or(false);
+                if is_buffer_block {
+                    DescriptorType::StorageBuffer
+                } else {
+                    DescriptorType::UniformBuffer
+                }
+            },
+            (STORAGE_CLASS_UNIFORM_CONSTANT, SpirvType::Image { dim, sampled }) => {
+                match (*dim == DIM_BUFFER, *sampled == 2) {
+                    (true, true) => DescriptorType::StorageTexelBuffer,
+                    (true, false) => DescriptorType::UniformTexelBuffer,
+                    (false, true) => DescriptorType::StorageImage,
+                    (false, false) => DescriptorType::SampledImage,
+                }
+            },
+            (STORAGE_CLASS_UNIFORM_CONSTANT, SpirvType::Sampler) => DescriptorType::Sampler,
+            (STORAGE_CLASS_UNIFORM_CONSTANT, SpirvType::SampledImage) => DescriptorType::CombinedImageSampler,
+            _ => return None,
+        };
+        let binding = DescriptorBinding {
+            variable: variable.id,
+            set,
+            binding,
+            descriptor_type,
+            descriptor_count,
+            name: self.names.get(&variable.id).cloned(),
+            block: self.block_layout(element),
+        };
+        Some(binding)
+    }
+
+    fn block_layout(&self, type_id: u32) -> Option<BlockLayout> {
+        let members = match self.types.get(&type_id)? {
+            SpirvType::Struct { members } => members,
+            _ => return None,
+        };
+        let mut runtime_array_stride = None;
+        let members: Vec<BlockMember> = members.iter()
+            .enumerate()
+            .map(|(index, &member)| {
+                let key = (type_id, index as u32);
+                let decorations = self.member_decorations.get(&key);
+                let offset = decorations.and_then(|v| v.offset).unwrap_or(0) as usize;
+                let matrix_stride = decorations.and_then(|v| v.matrix_stride);
+                let size = match self.types.get(&member) {
+                    Some(SpirvType::RuntimeArray { element }) => {
+                        runtime_array_stride = self.array_stride(member)
+                            .or_else(|| self.type_size(*element, None));
+                        0
+                    },
+                    _ => self.type_size(member, matrix_stride).unwrap_or(0),
+                };
+                BlockMember {
+                    name: self.member_names.get(&key).cloned(),
+                    offset,
+                    size,
+                    scalar_type: self.scalar_type(member),
+                }
+            })
+            .collect();
+        let size = members.iter()
+            .map(|v| v.offset + v.size)
+            .max()
+            .unwrap_or(0);
+        let layout = BlockLayout {
+            name: self.names.get(&type_id).cloned(),
+            size,
+            runtime_array_stride,
+            members,
+        };
+        Some(layout)
+    }
+
+    fn type_size(&self, type_id: u32, matrix_stride: Option<u32>) -> Option<usize> {
+        match self.types.get(&type_id)? {
+            SpirvType::Bool => Some(4),
+            SpirvType::Int { width, .. } => Some(*width as usize / 8),
+            SpirvType::Float { width } => Some(*width as usize / 8),
+            SpirvType::Vector { component, count } => {
+                Some(self.type_size(*component, None)? * *count as usize)
+            },
+            SpirvType::Matrix { column, count } => {
+                let column_size = match matrix_stride {
+                    Some(stride) => stride as usize,
+                    None => self.type_size(*column, None)?,
+                };
+                Some(column_size * *count as usize)
+            },
+            SpirvType::Array { element, length } => {
+                let length = self.constants.get(length)?.value as usize;
+                let stride = match self.array_stride(type_id) {
+                    Some(stride) => stride,
+                    None => self.type_size(*element, matrix_stride)?,
+                };
+                Some(stride * length)
+            },
+            SpirvType::Struct { .. } => {
+                self.block_layout(type_id).map(|v| v.size)
+            },
+            _ => None,
+        }
+    }
+
+    fn array_stride(&self, type_id: u32) -> Option<usize> {
+        self.decorations.get(&type_id)
+            .and_then(|v| v.array_stride)
+            .map(|v| v as usize)
+    }
+
+    fn scalar_type(&self, type_id: u32) -> Option<ScalarType> {
+        match self.types.get(&type_id)? {
+            SpirvType::Bool => Some(ScalarType::Bool),
+            SpirvType::Int { width, signed } => Some(ScalarType::Int { width: *width, signed: *signed }),
+            SpirvType::Float { width } => Some(ScalarType::Float { width: *width }),
+            _ => None,
+        }
+    }
+
+    fn pointee(&self, type_id: u32) -> Option<u32> {
+        match self.types.get(&type_id)? {
+            SpirvType::Pointer { pointee } => Some(*pointee),
+            _ => None,
+        }
+    }
+}
+
+// literal strings are nul-terminated UTF-8 packed into words in little-endian order
+fn parse_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words.iter()
+        .flat_map(|v| v.to_le_bytes().to_vec())
+        .take_while(|&v| v != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// the number of words of SPIR-V embedded by `include_spirv!`
+#[doc(hidden)]
+pub const fn spirv_word_count(bytes: &[u8]) -> usize {
+    if bytes.is_empty() || !bytes.len().is_multiple_of(4) {
+        panic!("SPIR-V code size is not a non-zero multiple of 4");
+    }
+    bytes.len() / 4
+}
+
+// converts SPIR-V embedded by `include_spirv!` into host-endian words,
+// rejecting big-endian modules and modules without a compute entry point at compile time
+#[doc(hidden)]
+pub const fn spirv_words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    let mut index = 0;
+    while index < N {
+        let offset = index * 4;
+        words[index] = u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+        index += 1;
+    }
+    if N < SPIRV_HEADER_WORD_COUNT {
+        panic!("SPIR-V code is shorter than its header");
+    }
+    // modules written in big-endian fail as they do at runtime with `ShaderEndiannessMismatch`
+    if words[0] == SPIRV_MAGIC_NUMBER.swap_bytes() {
+        panic!("SPIR-V module is written in big-endian");
+    }
+    if words[0] != SPIRV_MAGIC_NUMBER {
+        panic!("SPIR-V magic number not found");
+    }
+    let mut position = SPIRV_HEADER_WORD_COUNT;
+    while position < N {
+        let word_count = (words[position] >> 16) as usize;
+        let opcode = words[position] & 0xffff;
+        if word_count == 0 || position + word_count > N {
+            panic!("SPIR-V code is not well-formed");
+        }
+        if opcode == OP_ENTRY_POINT && word_count > 1 && words[position + 1] == EXECUTION_MODEL_GL_COMPUTE {
+            return words
+        }
+        position += word_count;
+    }
+    panic!("SPIR-V module has no compute entry point");
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let word_count = operands.len() as u32 + 1;
+        let mut words = vec![(word_count << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    // a nul-terminated literal string padded to words
+    fn string(value: &str) -> Vec<u32> {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize((bytes.len() + 1).next_multiple_of(4), 0);
+        bytes.chunks_exact(4)
+            .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+            .collect()
+    }
+
+    fn name(id: u32, value: &str) -> Vec<u32> {
+        let mut operands = vec![id];
+        operands.extend(string(value));
+        instruction(OP_NAME, &operands)
+    }
+
+    fn member_name(id: u32, member: u32, value: &str) -> Vec<u32> {
+        let mut operands = vec![id, member];
+        operands.extend(string(value));
+        instruction(OP_MEMBER_NAME, &operands)
+    }
+
+    fn module(bound: u32, instructions: Vec<Vec<u32>>) -> Vec<u32> {
+        let mut words = vec![SPIRV_MAGIC_NUMBER, 0x0001_0300, 0, bound, 0];
+        words.extend(instructions.into_iter().flatten());
+        words
+    }
+
+    fn words(bytes: &[u8]) -> Vec<u32> {
+        bytes.chunks_exact(4)
+            .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+            .collect()
+    }
+
+    // a compute shader of local_size_x = 64 declaring
+    // `layout(push_constant) uniform Params { uint count_x; float scale; uint base_x; float16_t bias; double weight; }`
+    // and `layout(constant_id = 0) const uint WIDTH`, `layout(constant_id = 1) const float SCALE`
+    pub(crate) fn named_module() -> Vec<u32> {
+        let members = [("count_x", 0), ("scale", 4), ("base_x", 8), ("bias", 12), ("weight", 16)];
+        let mut instructions = vec![
+            instruction(OP_CAPABILITY, &[1]),
+            instruction(OP_CAPABILITY, &[9]),
+            instruction(OP_CAPABILITY, &[10]),
+            instruction(14, &[0, 1]),
+        ];
+        let mut entry_point = vec![EXECUTION_MODEL_GL_COMPUTE, 1];
+        entry_point.extend(string("main"));
+        instructions.push(instruction(OP_ENTRY_POINT, &entry_point));
+        instructions.push(instruction(OP_EXECUTION_MODE, &[1, EXECUTION_MODE_LOCAL_SIZE, 64, 1, 1]));
+        instructions.push(name(8, "Params"));
+        for (index, (member, _)) in members.iter().enumerate() {
+            instructions.push(member_name(8, index as u32, member));
+        }
+        instructions.push(name(11, "WIDTH"));
+        instructions.push(name(12, "SCALE"));
+        instructions.push(instruction(OP_DECORATE, &[8, 2]));
+        for (index, (_, offset)) in members.iter().enumerate() {
+            instructions.push(instruction(OP_MEMBER_DECORATE, &[8, index as u32, DECORATION_OFFSET, *offset]));
+        }
+        instructions.push(instruction(OP_DECORATE, &[11, DECORATION_SPEC_ID, 0]));
+        instructions.push(instruction(OP_DECORATE, &[12, DECORATION_SPEC_ID, 1]));
+        instructions.extend(vec![
+            // void, fn() -> void, uint, float, float16_t, double
+            instruction(19, &[2]),
+            instruction(33, &[3, 2]),
+            instruction(OP_TYPE_INT, &[4, 32, 0]),
+            instruction(OP_TYPE_FLOAT, &[5, 32]),
+            instruction(OP_TYPE_FLOAT, &[6, 16]),
+            instruction(OP_TYPE_FLOAT, &[7, 64]),
+            instruction(OP_TYPE_STRUCT, &[8, 4, 5, 4, 6, 7]),
+            instruction(OP_TYPE_POINTER, &[9, STORAGE_CLASS_PUSH_CONSTANT, 8]),
+            instruction(OP_VARIABLE, &[9, 10, STORAGE_CLASS_PUSH_CONSTANT]),
+            instruction(OP_SPEC_CONSTANT, &[4, 11, 8]),
+            instruction(OP_SPEC_CONSTANT, &[5, 12, 1.0f32.to_bits()]),
+            instruction(OP_FUNCTION, &[2, 1, 0, 3]),
+            // OpLabel, OpReturn
+            instruction(248, &[13]),
+            instruction(253, &[]),
+            instruction(OP_FUNCTION_END, &[]),
+        ]);
+        module(14, instructions)
+    }
+
+    #[test]
+    fn parse_fibonacci() {
+        let interface = ShaderInterface::parse(&words(include_bytes!("../data/fibonacci.comp.spv"))).unwrap();
+        let entry_point = interface.entry_point("main").unwrap();
+        assert_eq!(entry_point.execution_model(), ExecutionModel::GLCompute);
+        assert_eq!(entry_point.local_size(), [1, 1, 1]);
+        assert_eq!(interface.bindings().len(), 1);
+        let binding = interface.binding(0, 0).unwrap();
+        assert_eq!(binding.descriptor_type(), DescriptorType::StorageBuffer);
+        assert_eq!(binding.block().unwrap().runtime_array_stride(), Some(4));
+        assert!(interface.push_constants().is_none());
+        assert!(interface.specialization_constants().is_empty());
+    }
+
+    #[test]
+    fn parse_merge() {
+        let interface = ShaderInterface::parse(&words(include_bytes!("../data/merge.comp.spv"))).unwrap();
+        let block = interface.push_constants().unwrap();
+        assert_eq!(block.size(), 4);
+        assert_eq!(block.members()[0].offset(), 0);
+        assert_eq!(block.members()[0].scalar_type(), Some(ScalarType::Int { width: 32, signed: false }));
+        let uint = ScalarType::Int { width: 32, signed: false };
+        for id in 0..2 {
+            assert_eq!(interface.specialization_constant(id).unwrap().scalar_type(), uint);
+        }
+        assert!(interface.specialization_constant(2).is_none());
+    }
+
+    #[test]
+    fn parse_names() {
+        let interface = ShaderInterface::parse(&named_module()).unwrap();
+        assert_eq!(interface.entry_point("main").unwrap().local_size(), [64, 1, 1]);
+        let block = interface.push_constants().unwrap();
+        assert_eq!(block.name(), Some("Params"));
+        assert_eq!(block.size(), 24);
+        let members: Vec<_> = block.members().iter()
+            .map(|v| (v.name().unwrap(), v.offset(), v.size()))
+            .collect();
+        assert_eq!(members, [("count_x", 0, 4), ("scale", 4, 4), ("base_x", 8, 4), ("bias", 12, 2), ("weight", 16, 8)]);
+        assert_eq!(block.members()[3].scalar_type(), Some(ScalarType::Float { width: 16 }));
+        let width = interface.specialization_constant_by_name("WIDTH").unwrap();
+        assert_eq!(width.id(), 0);
+        let scale = interface.specialization_constant_by_name("SCALE").unwrap();
+        assert_eq!((scale.id(), scale.scalar_type()), (1, ScalarType::Float { width: 32 }));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let code = named_module();
+        let error = ShaderInterface::parse(&code[..4]).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderTruncated));
+        // cut in the middle of the last instruction
+        let error = ShaderInterface::parse(&code[..code.len() - 2]).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderTruncated));
+        let swapped: Vec<u32> = code.iter().map(|v| v.swap_bytes()).collect();
+        let error = ShaderInterface::parse(&swapped).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderEndiannessMismatch));
+        let mut wrong_magic = code.clone();
+        wrong_magic[0] = 0xdeadbeef;
+        let error = ShaderInterface::parse(&wrong_magic).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderMagicNumberMismatch(0xdeadbeef)));
+        let mut small_bound = code.clone();
+        small_bound[SPIRV_HEADER_BOUND] = 10;
+        let error = ShaderInterface::parse(&small_bound).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderIdOutOfBound { bound: 10, .. }));
+        let mut zero_word_count = code;
+        zero_word_count[SPIRV_HEADER_WORD_COUNT] = 0;
+        let error = ShaderInterface::parse(&zero_word_count).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderMalformed));
+    }
+
+    #[test]
+    fn parse_without_compute_entry_point() {
+        // a vertex entry point only
+        let mut entry_point = vec![0, 1];
+        entry_point.extend(string("main"));
+        let code = module(4, vec![
+            instruction(OP_CAPABILITY, &[1]),
+            instruction(14, &[0, 1]),
+            instruction(OP_ENTRY_POINT, &entry_point),
+            instruction(19, &[2]),
+            instruction(33, &[3, 2]),
+        ]);
+        let error = ShaderInterface::parse(&code).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::ShaderComputeEntryPointNotFound));
+    }
+
+    #[test]
+    fn spirv_words_match_runtime_words() {
+        const BYTES: &[u8] = include_bytes!("../data/merge.comp.spv");
+        const WORDS: [u32; spirv_word_count(BYTES)] = spirv_words::<{ spirv_word_count(BYTES) }>(BYTES);
+        assert_eq!(WORDS.to_vec(), words(BYTES));
+    }
+
+    #[test]
+    #[should_panic(expected = "big-endian")]
+    fn spirv_words_rejects_big_endian() {
+        let bytes: Vec<u8> = named_module().iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        spirv_words::<64>(&bytes[..256]);
+    }
+}
@@ -41,7 +41,9 @@ macro_rules! binding_value {
 }
 
 pub struct BufferLayout<T0, T1 = (), T2 = (), T3 = ()> {
+    #[allow(dead_code)]
     bindings: (T0, T1, T2, T3),
+    #[allow(dead_code)]
     size: usize,
     entries: Vec<usize>,
 }
@@ -126,6 +128,7 @@ impl<T> BindingValue<T> {
 }
 
 pub struct BindingVariant<T> {
+    #[allow(dead_code)]
     variant: T,
     size: usize,
 }
@@ -278,6 +281,22 @@ impl<VariantType> BufferBindingView<VariantType> {
         };
         Arc::new(binding)
     }
+
+    // the address of the binding region for shaders to reach it by `GL_EXT_buffer_reference`,
+    // e.g. passed as a uint64_t push constant. needs `Feature::BufferDeviceAddress`
+    pub fn device_address(&self) -> Result<u64> {
+        self.buffer.staging_buffer().region_device_address(self.region_index)
+    }
+
+    #[inline]
+    pub(crate) fn region_index(&self) -> usize {
+        self.region_index
+    }
+
+    #[inline]
+    pub(crate) fn buffer(&self) -> &Arc<Buffer> {
+        &self.buffer
+    }
 }
 
 impl<ValueType> BufferBindingView<BindingValue<ValueType>> {
@@ -8,7 +8,22 @@ use libc::{c_char, c_float, size_t, c_void};
 use std::ffi::{CStr, CString};
 use std::ptr;
 
-const VK_API_VERSION_1_1: u32 = 4198400;
+pub const VK_API_VERSION_1_1: u32 = 4198400;
+pub const VK_API_VERSION_1_2: u32 = 4202496;
+
+// the Vulkan version the instance requests, a device is used at the lower of it and its own apiVersion.
+// 1.2 needs no newer loader than 1.1 does, 1.0 implementations reject both and the later ones accept any version
+pub const INSTANCE_API_VERSION: u32 = VK_API_VERSION_1_2;
+
+#[inline]
+pub fn vk_version_major(version: u32) -> u32 {
+    version >> 22
+}
+
+#[inline]
+pub fn vk_version_minor(version: u32) -> u32 {
+    (version >> 12) & 0x3ff
+}
 
 impl VkApplicationInfo {
     pub fn new(
@@ -24,7 +39,7 @@ impl VkApplicationInfo {
             applicationVersion: application_version,
             pEngineName: engine_name,
             engineVersion: engine_version,
-            apiVersion: VK_API_VERSION_1_1,
+            apiVersion: INSTANCE_API_VERSION,
         }
     }
 }
@@ -85,7 +100,8 @@ impl VkDeviceQueueCreateInfo {
 impl VkDeviceCreateInfo {
     pub fn new(
         create_queue_info_count: u32, 
-        create_queue_infos: *const VkDeviceQueueCreateInfo) -> Self {
+        create_queue_infos: *const VkDeviceQueueCreateInfo,
+        enabled_features: *const VkPhysicalDeviceFeatures) -> Self {
 
         VkDeviceCreateInfo {
             sType: VkStructureType::VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
@@ -97,11 +113,117 @@ impl VkDeviceCreateInfo {
             ppEnabledLayerNames: ptr::null(),
             enabledExtensionCount: 0,
             ppEnabledExtensionNames: ptr::null(),
-            pEnabledFeatures: ptr::null(),
+            pEnabledFeatures: enabled_features,
+        }
+    }
+}
+
+impl VkDeviceCreateInfo {
+    // the features given by the pNext chain of VkPhysicalDeviceFeatures2
+    pub fn with_features_chain(
+        create_queue_info_count: u32, 
+        create_queue_infos: *const VkDeviceQueueCreateInfo,
+        features: *const VkPhysicalDeviceFeatures2,
+        enabled_extension_count: u32,
+        enabled_extension_names: *const *const c_char) -> Self {
+
+        let mut info = Self::new(create_queue_info_count, create_queue_infos, ptr::null());
+        info.pNext = features as *const c_void;
+        info.enabledExtensionCount = enabled_extension_count;
+        info.ppEnabledExtensionNames = enabled_extension_names;
+        info
+    }
+}
+
+impl VkPhysicalDeviceFeatures {
+    // all the features disabled
+    pub fn none() -> Self {
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+impl VkPhysicalDeviceFeatures2 {
+    pub fn new(features: VkPhysicalDeviceFeatures, next: *mut c_void) -> Self {
+        VkPhysicalDeviceFeatures2 {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2,
+            pNext: next,
+            features,
+        }
+    }
+}
+
+impl VkPhysicalDeviceVulkan11Features {
+    // all the features disabled
+    pub fn none() -> Self {
+        let mut features: Self = unsafe { std::mem::zeroed() };
+        features.sType = VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_1_FEATURES;
+        features
+    }
+}
+
+impl VkPhysicalDeviceSubgroupSizeControlFeaturesEXT {
+    // all the features disabled
+    pub fn none() -> Self {
+        let mut features: Self = unsafe { std::mem::zeroed() };
+        features.sType = VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_SUBGROUP_SIZE_CONTROL_FEATURES_EXT;
+        features
+    }
+}
+
+impl VkPhysicalDeviceProperties2 {
+    pub fn new(next: *mut c_void) -> Self {
+        let mut properties: Self = unsafe { std::mem::zeroed() };
+        properties.sType = VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_PROPERTIES_2;
+        properties.pNext = next;
+        properties
+    }
+}
+
+impl VkPhysicalDeviceSubgroupProperties {
+    pub fn new(next: *mut c_void) -> Self {
+        VkPhysicalDeviceSubgroupProperties {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_SUBGROUP_PROPERTIES,
+            pNext: next,
+            subgroupSize: 0,
+            supportedStages: 0,
+            supportedOperations: 0,
+            quadOperationsInAllStages: VK_FALSE,
+        }
+    }
+}
+
+impl VkPhysicalDeviceSubgroupSizeControlPropertiesEXT {
+    pub fn new() -> Self {
+        VkPhysicalDeviceSubgroupSizeControlPropertiesEXT {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_SUBGROUP_SIZE_CONTROL_PROPERTIES_EXT,
+            pNext: ptr::null_mut(),
+            minSubgroupSize: 0,
+            maxSubgroupSize: 0,
+            maxComputeWorkgroupSubgroups: 0,
+            requiredSubgroupSizeStages: 0,
+        }
+    }
+}
+
+impl VkPipelineShaderStageRequiredSubgroupSizeCreateInfoEXT {
+    pub fn new(required_subgroup_size: u32) -> Self {
+        VkPipelineShaderStageRequiredSubgroupSizeCreateInfoEXT {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_REQUIRED_SUBGROUP_SIZE_CREATE_INFO_EXT,
+            pNext: ptr::null_mut(),
+            requiredSubgroupSize: required_subgroup_size,
         }
     }
 }
 
+impl VkPhysicalDeviceVulkan12Features {
+    // all the features disabled
+    pub fn none() -> Self {
+        let mut features: Self = unsafe { std::mem::zeroed() };
+        features.sType = VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_2_FEATURES;
+        features
+    }
+}
+
 impl VkCommandPoolCreateInfo {
     pub fn new(queue_family_index: u32) -> Self {
         VkCommandPoolCreateInfo {
@@ -143,6 +265,27 @@ impl VkMemoryAllocateInfo {
     }
 }
 
+impl VkMemoryAllocateFlagsInfo {
+    pub fn new(flags: VkMemoryAllocateFlags) -> Self {
+        VkMemoryAllocateFlagsInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_FLAGS_INFO,
+            pNext: ptr::null(),
+            flags,
+            deviceMask: 0,
+        }
+    }
+}
+
+impl VkBufferDeviceAddressInfo {
+    pub fn new(buffer: VkBuffer) -> Self {
+        VkBufferDeviceAddressInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_BUFFER_DEVICE_ADDRESS_INFO,
+            pNext: ptr::null(),
+            buffer,
+        }
+    }
+}
+
 impl VkMappedMemoryRange {
     pub fn new(memory: VkDeviceMemory, offset: VkDeviceSize, size: VkDeviceSize) -> Self {
         VkMappedMemoryRange {
@@ -280,16 +423,40 @@ impl VkDescriptorSetLayoutCreateInfo {
     }
 }
 
+impl VkDescriptorSetLayoutBindingFlagsCreateInfo {
+    pub fn new(count: u32, binding_flags: *const VkDescriptorBindingFlags) -> Self {
+        VkDescriptorSetLayoutBindingFlagsCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO,
+            pNext: ptr::null(),
+            bindingCount: count,
+            pBindingFlags: binding_flags,
+        }
+    }
+}
+
 impl VkPipelineLayoutCreateInfo {
-    pub fn new(count: u32, layouts: *const VkDescriptorSetLayout) -> Self {
+    pub fn new(count: u32, 
+        layouts: *const VkDescriptorSetLayout, 
+        range_count: u32, 
+        ranges: *const VkPushConstantRange) -> Self {
         VkPipelineLayoutCreateInfo {
             sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO,
             pNext: ptr::null(),
             flags: 0,
             setLayoutCount: count,
             pSetLayouts: layouts,
-            pushConstantRangeCount: 0,
-            pPushConstantRanges: ptr::null(),
+            pushConstantRangeCount: range_count,
+            pPushConstantRanges: ranges,
+        }
+    }
+}
+
+impl VkPushConstantRange {
+    pub fn new(stage_flags: VkShaderStageFlags, offset: u32, size: u32) -> Self {
+        VkPushConstantRange {
+            stageFlags: stage_flags,
+            offset,
+            size,
         }
     }
 }
@@ -306,6 +473,17 @@ impl VkDescriptorSetAllocateInfo {
     }
 }
 
+impl VkDescriptorSetVariableDescriptorCountAllocateInfo {
+    pub fn new(set_count: u32, descriptor_counts: *const u32) -> Self {
+        VkDescriptorSetVariableDescriptorCountAllocateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DESCRIPTOR_SET_VARIABLE_DESCRIPTOR_COUNT_ALLOCATE_INFO,
+            pNext: ptr::null(),
+            descriptorSetCount: set_count,
+            pDescriptorCounts: descriptor_counts,
+        }
+    }
+}
+
 impl VkDescriptorBufferInfo {
     pub fn new(buffer: VkBuffer, offset: VkDeviceSize, range: VkDeviceSize) -> Self {
         VkDescriptorBufferInfo {
@@ -339,13 +517,26 @@ impl VkWriteDescriptorSet {
 }
 
 impl VkPipelineCacheCreateInfo {
-    pub fn new() -> Self {
+    pub fn new(initial_data_size: size_t, initial_data: *const c_void) -> Self {
         VkPipelineCacheCreateInfo {
             sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_CACHE_CREATE_INFO,
             pNext: ptr::null(),
             flags: 0,
-            initialDataSize: 0,
-            pInitialData: ptr::null(),
+            initialDataSize: initial_data_size,
+            pInitialData: initial_data,
+        }
+    }
+}
+
+impl VkQueryPoolCreateInfo {
+    pub fn new(query_type: VkQueryType, query_count: u32) -> Self {
+        VkQueryPoolCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
+            pNext: ptr::null(),
+            flags: 0,
+            queryType: query_type,
+            queryCount: query_count,
+            pipelineStatistics: 0,
         }
     }
 }
@@ -370,12 +561,13 @@ impl VkPipelineShaderStageCreateInfo {
 
 impl VkComputePipelineCreateInfo {
     pub fn new(
+        flags: VkPipelineCreateFlags,
         stage: VkPipelineShaderStageCreateInfo,
         layout: VkPipelineLayout) -> Self {
         VkComputePipelineCreateInfo {
             sType: VkStructureType::VK_STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO,
             pNext: ptr::null(),
-            flags: 0,
+            flags,
             stage: stage,
             layout: layout,
             basePipelineHandle: ptr::null_mut(),
@@ -0,0 +1,121 @@
+use filum::{Context, BufferViewBuilder, PipelineBuilder, DispatchBuilder};
+use std::sync::Arc;
+use std::thread;
+
+const NUM_THREADS: usize = 4;
+const NUM_ELEMENTS: usize = 256;
+
+// fibonacci(0) = 0 and fibonacci(1) = 1, so dispatching them any number of times leaves them as they are
+fn fixed_points() -> Vec<u32> {
+    (0..NUM_ELEMENTS as u32).map(|v| v % 2).collect()
+}
+
+#[test]
+#[ignore = "requires a Vulkan device, run with --ignored"]
+fn submit_shared_pipeline() {
+    let context = Context::new().unwrap();
+    let buffer_view = BufferViewBuilder::new(&context)
+        .bind_array::<u32>(NUM_ELEMENTS)
+        .build()
+        .unwrap();
+    let pipeline = PipelineBuilder::new(buffer_view.buffer())
+        .shader("data/fibonacci.comp.spv")
+        .build()
+        .unwrap();
+    let binding = buffer_view.binding();
+    binding.update_array_copying(&fixed_points());
+    thread::scope(|scope| {
+        for _ in 0..NUM_THREADS {
+            let pipeline = Arc::clone(&pipeline);
+            scope.spawn(move || {
+                let dispatch = DispatchBuilder::new(&pipeline)
+                    .workgroup_count(NUM_ELEMENTS, 1, 1)
+                    .build()
+                    .unwrap();
+                for _ in 0..16 {
+                    dispatch.submit().unwrap();
+                    dispatch.wait();
+                }
+            });
+        }
+        // host accesses in the meantime block until the submissions in flight complete
+        scope.spawn(|| {
+            for _ in 0..16 {
+                let mut v = vec![0u32; NUM_ELEMENTS];
+                binding.fetch_array_copying(&mut v);
+                assert_eq!(v, fixed_points());
+            }
+        });
+    });
+    let mut v = vec![0u32; NUM_ELEMENTS];
+    binding.fetch_array_copying(&mut v);
+    assert_eq!(v, fixed_points());
+}
+
+#[test]
+#[ignore = "requires a Vulkan device, run with --ignored"]
+fn dispatch_shared_pipeline() {
+    let context = Context::new().unwrap();
+    let buffer_view = BufferViewBuilder::new(&context)
+        .bind_array::<u32>(NUM_ELEMENTS)
+        .build()
+        .unwrap();
+    let pipeline = PipelineBuilder::new(buffer_view.buffer())
+        .shader("data/fibonacci.comp.spv")
+        .build()
+        .unwrap();
+    let binding = buffer_view.binding();
+    binding.update_array_copying(&fixed_points());
+    thread::scope(|scope| {
+        for _ in 0..NUM_THREADS {
+            scope.spawn(|| {
+                for _ in 0..16 {
+                    pipeline.dispatch(NUM_ELEMENTS).unwrap();
+                }
+            });
+        }
+    });
+    let mut v = vec![0u32; NUM_ELEMENTS];
+    binding.fetch_array_copying(&mut v);
+    assert_eq!(v, fixed_points());
+}
+
+#[test]
+#[ignore = "requires a Vulkan device, run with --ignored"]
+fn build_pipelines_concurrently() {
+    let context = Context::new().unwrap();
+    let shader_module = context.shader_module("data/fibonacci.comp.spv").unwrap();
+    let expected: Vec<u32> = vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 377, 610];
+    let pipelines = thread::scope(|scope| {
+        let handles: Vec<_> = (0..NUM_THREADS)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut v: Vec<u32> = (0..expected.len() as u32).collect();
+                    let buffer_view = BufferViewBuilder::new(&context)
+                        .bind_array::<u32>(v.len())
+                        .build()
+                        .unwrap();
+                    let pipeline = PipelineBuilder::new(buffer_view.buffer())
+                        .shader_module(&shader_module)
+                        .build()
+                        .unwrap();
+                    let binding = buffer_view.binding();
+                    binding.update_array_copying(&v);
+                    pipeline.dispatch(v.len()).unwrap();
+                    binding.fetch_array_copying(&mut v);
+                    assert_eq!(v, expected);
+                    pipeline
+                })
+            })
+            .collect();
+        handles.into_iter()
+            .map(|v| v.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    // the pipelines built the same at once share the one compiled first
+    let stats = context.pipeline_stats();
+    assert_eq!(stats.misses(), 1);
+    assert_eq!(stats.hits(), NUM_THREADS as u64 - 1);
+    assert_eq!(stats.entries(), 1);
+    drop(pipelines);
+}